@@ -0,0 +1,269 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_pkv::PkvStore;
+use bevy_tweening::{Animator, Delay};
+use serde::{Deserialize, Serialize};
+
+use crate::{GameState, is_headless, Labels, spawn_sprite, spawn_text};
+use crate::button::spawn_button;
+use crate::cooking::OrderNumber;
+use crate::difficulty::{DifficultyPreset, GameMode};
+use crate::ingredients::Menu;
+use crate::input::KeyboardReleaseEvent;
+use crate::loading::{FontAssets, TextureAssets};
+use crate::order::{BurgerFinishedEvent, MenuOnDisplay, Order};
+use crate::replay::ReplayRecorder;
+use crate::score::Score;
+use crate::stats::RunStats;
+use crate::tween::{EV_DELETE, tween_position, TWEEN_TIME};
+
+pub struct AchievementsPlugin;
+
+/// [`PkvStore`] key of the [`Achievements`]
+pub const ACHIEVEMENTS: &str = "achievements";
+/// Time a toast stays on screen
+const TOAST_TIME: u64 = 2500;
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum Achievement {
+    Chain10,
+    TripleMeat,
+    MaxBurger,
+    FiftyOrders,
+    FullMenu,
+    MadnessSurvivor,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 6] = [
+        Achievement::Chain10,
+        Achievement::TripleMeat,
+        Achievement::MaxBurger,
+        Achievement::FiftyOrders,
+        Achievement::FullMenu,
+        Achievement::MadnessSurvivor,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Achievement::Chain10 => "10 CHAIN",
+            Achievement::TripleMeat => "TRIPLE MEAT",
+            Achievement::MaxBurger => "SKYSCRAPER",
+            Achievement::FiftyOrders => "FIFTY ORDERS",
+            Achievement::FullMenu => "FULL MENU",
+            Achievement::MadnessSurvivor => "MADNESS 3:00",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Achievement::Chain10 => "Serve 10 perfect burgers in a row",
+            Achievement::TripleMeat => "Serve a burger with three meats",
+            Achievement::MaxBurger => "Perfect burger of the biggest size",
+            Achievement::FiftyOrders => "Send 50 burgers in one game",
+            Achievement::FullMenu => "See every dish of the menu in one game",
+            Achievement::MadnessSurvivor => "Survive 3 minutes in Madness",
+        }
+    }
+}
+
+/// Achievements unlocked on this computer
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct Achievements {
+    pub unlocked: HashSet<Achievement>,
+}
+
+impl Achievements {
+    /// Returns `true` if the achievement wasn't unlocked yet
+    pub fn unlock(&mut self, achievement: Achievement) -> bool {
+        self.unlocked.insert(achievement)
+    }
+
+    pub fn is_unlocked(&self, achievement: Achievement) -> bool {
+        self.unlocked.contains(&achievement)
+    }
+}
+
+/// Event sent when an achievement is unlocked for the first time
+pub struct AchievementUnlockedEvent(pub Achievement);
+
+#[derive(Component)]
+struct AchievementsUi;
+
+#[derive(Component)]
+struct Toast;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Achievements>()
+            .add_event::<AchievementUnlockedEvent>()
+            .add_system_set(SystemSet::on_update(GameState::Cooking)
+                .after(Labels::LogicReceiver)
+                .before(Labels::UI)
+                .with_system(check_burger_achievements)
+                .with_system(check_game_achievements)
+            );
+
+        if is_headless(app) { return; }
+
+        app.add_startup_system(load_achievements)
+            .add_system(save_achievements)
+            .add_system(show_toast.after(save_achievements))
+            .add_system_set(SystemSet::on_enter(GameState::Achievements).with_system(setup_achievements_page))
+            .add_system_set(SystemSet::on_update(GameState::Achievements).with_system(handle_input))
+            .add_system_set(SystemSet::on_exit(GameState::Achievements).with_system(cleanup_achievements_page));
+    }
+}
+
+/// Unlocks the achievements reached in the player's own games
+#[derive(SystemParam)]
+struct Unlocker<'w, 's> {
+    achievements: ResMut<'w, Achievements>,
+    ev_unlocked: EventWriter<'w, 's, AchievementUnlockedEvent>,
+    mode: Res<'w, GameMode>,
+    recorder: Res<'w, ReplayRecorder>,
+}
+
+impl<'w, 's> Unlocker<'w, 's> {
+    /// Games of the bot or watched from a replay don't count, and Zen is only practice
+    fn counts(&self) -> bool {
+        self.recorder.is_player_game() && !self.mode.is_zen()
+    }
+
+    fn unlock(&mut self, achievement: Achievement) {
+        if self.achievements.unlock(achievement) {
+            self.ev_unlocked.send(AchievementUnlockedEvent(achievement));
+        }
+    }
+}
+
+fn check_burger_achievements(
+    mut unlocker: Unlocker,
+    mut ev_burger_finished: EventReader<BurgerFinishedEvent>,
+    score: Res<Score>,
+    order: Res<Order>,
+    menu: Res<Menu>,
+) {
+    if !unlocker.counts() {
+        ev_burger_finished.clear();
+        return;
+    }
+
+    for BurgerFinishedEvent { correct, .. } in ev_burger_finished.iter() {
+        if !correct { continue; }
+        if score.streak >= 10 { unlocker.unlock(Achievement::Chain10); }
+        if order.ingredients.iter().filter(|i| menu.is_meat(i)).count() >= 3 { unlocker.unlock(Achievement::TripleMeat); }
+        if order.ingredients.len() >= menu.max_size { unlocker.unlock(Achievement::MaxBurger); }
+    }
+}
+
+fn check_game_achievements(
+    mut unlocker: Unlocker,
+    order_nb: Res<OrderNumber>,
+    menu: Res<Menu>,
+    menu_on_display: Res<MenuOnDisplay>,
+    stats: Res<RunStats>,
+) {
+    if !unlocker.counts() { return; }
+
+    if order_nb.amount >= 50 { unlocker.unlock(Achievement::FiftyOrders); }
+    if menu.ingredients.iter().all(|i| menu_on_display.ingredients_seen.contains(i)) {
+        unlocker.unlock(Achievement::FullMenu);
+    }
+    if unlocker.mode.difficulty().preset == DifficultyPreset::Madness && stats.time_cooked >= 180. {
+        unlocker.unlock(Achievement::MadnessSurvivor);
+    }
+}
+
+fn load_achievements(mut achievements: ResMut<Achievements>, pkv: Res<PkvStore>) {
+    if let Ok(saved) = pkv.get::<Achievements>(ACHIEVEMENTS) {
+        *achievements = saved;
+    }
+}
+
+fn save_achievements(
+    achievements: Res<Achievements>,
+    mut ev_unlocked: EventReader<AchievementUnlockedEvent>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    if ev_unlocked.iter().count() > 0 {
+        let _ = pkv.set(ACHIEVEMENTS, &*achievements);
+    }
+}
+
+/// Slides the name of the achievement down from the top of the screen, then back up
+fn show_toast(
+    mut commands: Commands,
+    mut ev_unlocked: EventReader<AchievementUnlockedEvent>,
+    toasts: Query<(), With<Toast>>,
+    fonts: Res<FontAssets>,
+) {
+    let shown = toasts.iter().count();
+    for (i, AchievementUnlockedEvent(achievement)) in ev_unlocked.iter().enumerate() {
+        let hidden = Vec2::new(112., 188.);
+        let visible = Vec2::new(112., 160. - 10. * (shown + i) as f32);
+        spawn_text(&mut commands, &fonts, format!("UNLOCKED: {}", achievement.name()), hidden, Color::WHITE)
+            .insert(Toast)
+            .insert(Animator::new(
+                tween_position(hidden, visible, 5., TWEEN_TIME)
+                    .then(Delay::new(Duration::from_millis(TOAST_TIME)))
+                    .then(tween_position(visible, hidden, 5., TWEEN_TIME).with_completed_event(EV_DELETE))
+            ));
+    }
+}
+
+fn setup_achievements_page(
+    mut commands: Commands,
+    achievements: Res<Achievements>,
+    textures: Res<TextureAssets>,
+    fonts: Res<FontAssets>,
+) {
+    spawn_sprite(&mut commands, textures.background.clone(), Vec3::ZERO).insert(AchievementsUi);
+    spawn_sprite(&mut commands, textures.counter.clone(), Vec3::new(0., 0., 0.5)).insert(AchievementsUi);
+
+    let unlocked = Achievement::ALL.iter().filter(|a| achievements.is_unlocked(**a)).count();
+    spawn_text(
+        &mut commands, &fonts,
+        format!("ACHIEVEMENTS {}/{}", unlocked, Achievement::ALL.len()),
+        Vec2::new(16., 160.), Color::WHITE,
+    ).insert(AchievementsUi);
+
+    for (i, achievement) in Achievement::ALL.iter().enumerate() {
+        let (name_color, description_color) = match achievements.is_unlocked(*achievement) {
+            true => (Color::rgb(1., 0.85, 0.3), Color::WHITE),
+            false => (Color::GRAY, Color::GRAY),
+        };
+        let y = 140. - 18. * i as f32;
+        spawn_text(&mut commands, &fonts, achievement.name().to_string(), Vec2::new(16., y), name_color)
+            .insert(AchievementsUi);
+        spawn_text(&mut commands, &fonts, achievement.description().to_string(), Vec2::new(100., y), description_color)
+            .insert(AchievementsUi);
+    }
+
+    let (button, _) = spawn_button(&mut commands, Vec2::new(124., 12.), ' ', &textures, &fonts, false);
+    commands.entity(button).insert(AchievementsUi);
+    spawn_text(&mut commands, &fonts, "back".to_string(), Vec2::new(140., 14.), Color::WHITE).insert(AchievementsUi);
+}
+
+fn handle_input(
+    mut events: EventReader<KeyboardReleaseEvent>,
+    mut state: ResMut<State<GameState>>,
+) {
+    for KeyboardReleaseEvent(key) in events.iter() {
+        if *key == ' ' || *key == '<' {
+            state.set(GameState::TitleScreen).unwrap_or_default();
+        }
+    }
+}
+
+fn cleanup_achievements_page(
+    mut commands: Commands,
+    entities: Query<Entity, With<AchievementsUi>>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
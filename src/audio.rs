@@ -1,17 +1,24 @@
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 
+use crate::is_headless;
 use crate::loading::AudioAssets;
+use crate::settings::Settings;
 
 pub struct InternalAudioPlugin;
 
 impl Plugin for InternalAudioPlugin {
     fn build(&self, app: &mut App) {
+        app
+            .add_event::<PlayBgmEvent>()
+            .add_event::<PlaySfxEvent>();
+
+        if is_headless(app) { return; }
+
         app
             .add_plugin(AudioPlugin)
             .add_system(update_bgm)
-            .add_event::<PlayBgmEvent>()
-            .add_event::<PlaySfxEvent>()
+            .add_system(apply_volumes)
             .add_audio_channel::<BgmChannel>()
             .add_audio_channel::<SfxChannel>();
     }
@@ -48,6 +55,8 @@ pub enum SFX {
     CustomerSad,
     CustomerHappy,
     Chalk,
+    /// The menu is about to change
+    MenuWarning,
 }
 
 impl SFX {
@@ -63,6 +72,7 @@ impl SFX {
             SFX::CustomerSad => audio_assets.customer_sad.clone(),
             SFX::CustomerHappy => audio_assets.customer_happy.clone(),
             SFX::Chalk => audio_assets.chalk.clone(),
+            SFX::MenuWarning => audio_assets.menu_warning.clone(),
         }
     }
 }
@@ -79,6 +89,7 @@ fn update_bgm(
     mut bgm_events: EventReader<PlayBgmEvent>,
     mut sfx_events: EventReader<PlaySfxEvent>,
     audio_assets: Option<Res<AudioAssets>>,
+    settings: Res<Settings>,
     bgm_channel: Res<AudioChannel<BgmChannel>>,
     sfx_channel: Res<AudioChannel<SfxChannel>>,
 ) {
@@ -87,7 +98,7 @@ fn update_bgm(
     // Play BGMs
     for PlayBgmEvent(bgm) in bgm_events.iter() {
         bgm_channel.stop();
-        bgm_channel.set_volume(0.6);
+        bgm_channel.set_volume(settings.bgm_volume());
         bgm_channel
             .play(bgm.get_handle(&audio_assets.as_ref().unwrap()))
             .looped();
@@ -97,7 +108,19 @@ fn update_bgm(
 
     // Play SFXs
     for PlaySfxEvent(sfx) in sfx_events.iter() {
-        sfx_channel.set_volume(0.3);
+        sfx_channel.set_volume(settings.sfx_volume());
         sfx_channel.play(sfx.get_handle(&audio_assets.as_ref().unwrap()));
     }
-}
\ No newline at end of file
+}
+
+/// Sets the volume of the music already playing when the settings are loaded or changed
+fn apply_volumes(
+    settings: Res<Settings>,
+    bgm_channel: Res<AudioChannel<BgmChannel>>,
+    sfx_channel: Res<AudioChannel<SfxChannel>>,
+) {
+    if settings.is_changed() {
+        bgm_channel.set_volume(settings.bgm_volume());
+        sfx_channel.set_volume(settings.sfx_volume());
+    }
+}
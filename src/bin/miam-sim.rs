@@ -0,0 +1,204 @@
+//! Game balance simulator: plays thousands of games without rendering anything
+//! and prints the distributions of order lengths, scores and survival times.
+//!
+//! ```text
+//! cargo run --release --bin miam-sim -- --games 5000 --speed 3 --errors 0.1 --difficulty madness
+//! ```
+
+use std::collections::BTreeMap;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use miam::difficulty::Difficulty;
+use miam::headless::default_menu;
+use miam::ingredients::{Ingredient, Menu, MenuBook};
+use miam::restaurant::{next_menu_ingredient, write_on_menu};
+use miam::rng::GameRng;
+use miam::score::Score;
+
+/// Time between a burger and the next order (customer exit and entrance)
+const CUSTOMER_DELAY: f64 = 1.;
+
+/// Simulated player, typing like [`miam::bot::Bot`]: a wrong key is noticed,
+/// the burger is cleared and typed again from the beginning.
+struct Player {
+    keys_per_second: f64,
+    error_rate: f64,
+    reaction_time: f64,
+}
+
+impl Player {
+    /// Time needed to cook and send a burger of `size` ingredients
+    fn cook<R: Rng>(&self, size: usize, rng: &mut R) -> f64 {
+        let mut keys = 0;
+        let mut placed = 0;
+        while placed < size {
+            keys += 1;
+            if rng.gen_bool(self.error_rate) {
+                // Wrong ingredient, then backspace
+                keys += 1;
+                placed = 0;
+            } else {
+                placed += 1;
+            }
+        }
+        // Send the order
+        keys += 1;
+        self.reaction_time + keys as f64 / self.keys_per_second
+    }
+}
+
+struct GameResult {
+    score: i64,
+    burgers: u32,
+    best_streak: i64,
+    survival: f64,
+    order_sizes: Vec<usize>,
+}
+
+fn simulate(menu: &Menu, player: &Player, difficulty: &Difficulty, seed: u64, max_time: f64) -> GameResult {
+    let mut rng = GameRng::new(seed);
+    let mut player_rng = StdRng::seed_from_u64(seed.wrapping_add(1));
+
+    let mut on_display: Vec<Ingredient> = vec![];
+    for ingredient in menu.basic_ingredients(difficulty.starting_menu_size(), &mut rng.menu) {
+        write_on_menu(menu, &mut on_display, ingredient, difficulty.menu_size, &mut rng.menu);
+    }
+
+    let mut score = Score { lives: difficulty.lives, ..Score::default() };
+    let mut result = GameResult { score: 0, burgers: 0, best_streak: 0, survival: 0., order_sizes: vec![] };
+    let mut order_nb: u16 = 0;
+    let mut next_rotation = difficulty.menu_interval(0).as_secs_f64();
+
+    while score.lives > 0 && result.survival < max_time {
+        let order = menu.generate_order(&on_display, &mut rng.orders);
+        result.order_sizes.push(order.len());
+
+        let patience = difficulty.waiting_time(order.len(), order_nb).as_secs_f64();
+        let time = player.cook(order.len(), &mut player_rng);
+        if time <= patience {
+            order_nb += 1;
+            result.burgers += 1;
+            score.compute_on_success(time, order.len(), difficulty);
+            result.survival += time;
+        } else {
+            score.compute_on_failure();
+            result.survival += patience;
+        }
+        result.best_streak = result.best_streak.max(score.streak);
+        result.survival += CUSTOMER_DELAY;
+
+        // Menu rotation
+        while result.survival >= next_rotation {
+            let ingredient = next_menu_ingredient(menu, &on_display, &mut rng.menu);
+            write_on_menu(menu, &mut on_display, ingredient, difficulty.menu_size, &mut rng.menu);
+            next_rotation += difficulty.menu_interval(order_nb).as_secs_f64();
+        }
+    }
+
+    result.score = score.score;
+    result
+}
+
+fn main() {
+    let games = arg_value("--games").and_then(|s| s.parse::<u64>().ok()).unwrap_or(1000);
+    let seed = arg_value("--seed").and_then(|s| s.parse::<u64>().ok()).unwrap_or(0);
+    let max_time = arg_value("--max-time").and_then(|s| s.parse::<f64>().ok()).unwrap_or(1800.);
+    let difficulty = match arg_value("--difficulty").as_deref() {
+        None if has_flag("--madness") => Difficulty::madness(),
+        None | Some("classic") => Difficulty::classic(),
+        Some("easy") => Difficulty::easy(),
+        Some("madness") => Difficulty::madness(),
+        Some(path) => match Difficulty::from_file(path) {
+            Ok(difficulty) => difficulty,
+            Err(e) => {
+                eprintln!("Couldn't read the difficulty {}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+    };
+    let player = Player {
+        keys_per_second: arg_value("--speed").and_then(|s| s.parse::<f64>().ok()).unwrap_or(4.),
+        error_rate: arg_value("--errors").and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.05),
+        reaction_time: arg_value("--reaction").and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.5),
+    };
+    if player.keys_per_second <= 0. || !(0. ..1.).contains(&player.error_rate) {
+        eprintln!("--speed must be positive and --errors must be in [0, 1)");
+        std::process::exit(1);
+    }
+
+    let menu = match arg_value("--menu") {
+        Some(path) => match std::fs::read(&path).map_err(anyhow::Error::from).and_then(|bytes| MenuBook::from_bytes(&bytes)) {
+            Ok(mut book) => book.menus.remove(0),
+            Err(e) => {
+                eprintln!("Couldn't read the menu {}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+        None => default_menu(),
+    };
+
+    println!(
+        "{} games of \"{}\" ({}), {} keys/s, {} errors, {}s reaction time",
+        games, menu.name, difficulty.preset.name(),
+        player.keys_per_second, player.error_rate, player.reaction_time
+    );
+
+    let results: Vec<GameResult> = (0..games)
+        .map(|i| simulate(&menu, &player, &difficulty, seed.wrapping_add(i), max_time))
+        .collect();
+
+    let mut sizes = BTreeMap::new();
+    for size in results.iter().flat_map(|r| r.order_sizes.iter()) {
+        *sizes.entry(*size).or_insert(0) += 1;
+    }
+    print_histogram("Order length", &sizes);
+
+    print_distribution("Score", results.iter().map(|r| r.score as f64).collect());
+    print_distribution("Burgers served", results.iter().map(|r| r.burgers as f64).collect());
+    print_distribution("Longest streak", results.iter().map(|r| r.best_streak as f64).collect());
+    print_distribution("Survival time (s)", results.iter().map(|r| r.survival).collect());
+
+    let survivors = results.iter().filter(|r| r.survival >= max_time).count();
+    if survivors > 0 {
+        println!("\n{} games reached the time limit of {}s", survivors, max_time);
+    }
+}
+
+/// Prints the minimum, percentiles, maximum and mean of `values`
+fn print_distribution(name: &str, mut values: Vec<f64>) {
+    if values.is_empty() { return; }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let percentile = |p: f64| values[((values.len() - 1) as f64 * p).round() as usize];
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    println!(
+        "\n{}\n  min {:.1}  p10 {:.1}  p50 {:.1}  p90 {:.1}  max {:.1}  mean {:.1}",
+        name, values[0], percentile(0.1), percentile(0.5), percentile(0.9), values[values.len() - 1], mean
+    );
+}
+
+fn print_histogram(name: &str, counts: &BTreeMap<usize, u64>) {
+    let total: u64 = counts.values().sum();
+    let max = counts.values().copied().max().unwrap_or(1);
+    println!("\n{}", name);
+    for (value, count) in counts {
+        println!(
+            "  {:>3} {:>6.2}% {}",
+            value, 100. * *count as f64 / total as f64, "#".repeat((40 * count / max) as usize)
+        );
+    }
+}
+
+fn has_flag(flag: &str) -> bool {
+    std::env::args().any(|arg| arg == flag)
+}
+
+/// Returns the value following `flag` in the command line arguments
+fn arg_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
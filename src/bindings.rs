@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+use crate::{GameState, spawn_sprite, spawn_text};
+use crate::button::spawn_button;
+use crate::ingredients::Ingredient;
+use crate::input::KeyboardReleaseEvent;
+use crate::loading::{FontAssets, TextureAssets};
+
+pub struct BindingsPlugin;
+
+/// [`PkvStore`] key of the [`KeyBindings`]
+pub const KEY_BINDINGS: &str = "key_bindings";
+
+const HELP: &str = "press the key of an action to change it";
+
+/// An action of the cooking screen, typed with the key bound to it
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum Binding {
+    Ingredient(Ingredient),
+    Send,
+    Delete,
+}
+
+impl Binding {
+    pub fn all() -> impl Iterator<Item = Binding> {
+        Ingredient::ALL
+            .into_iter()
+            .map(Binding::Ingredient)
+            .chain([Binding::Send, Binding::Delete])
+    }
+
+    /// Key of the action in the game, see [`Ingredient::key`]
+    pub fn game_key(&self) -> char {
+        match self {
+            Binding::Ingredient(ingredient) => ingredient.key(),
+            Binding::Send => ' ',
+            Binding::Delete => '<',
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Ingredient(ingredient) => ingredient.name(),
+            Binding::Send => "Send".to_string(),
+            Binding::Delete => "Delete".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum KeyboardLayout {
+    Qwerty,
+    Azerty,
+    Dvorak,
+    Colemak,
+}
+
+impl KeyboardLayout {
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyboardLayout::Qwerty => "QWERTY",
+            KeyboardLayout::Azerty => "AZERTY",
+            KeyboardLayout::Dvorak => "DVORAK",
+            KeyboardLayout::Colemak => "COLEMAK",
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            KeyboardLayout::Qwerty => KeyboardLayout::Azerty,
+            KeyboardLayout::Azerty => KeyboardLayout::Dvorak,
+            KeyboardLayout::Dvorak => KeyboardLayout::Colemak,
+            KeyboardLayout::Colemak => KeyboardLayout::Qwerty,
+        }
+    }
+
+    /// Keys of the three rows of letters, from left to right.
+    ///
+    /// The AZERTY key typing '!' has no [`KeyCode`], that place keeps its QWERTY key.
+    fn rows(&self) -> [&'static str; 3] {
+        match self {
+            KeyboardLayout::Qwerty => ["qwertyuiop", "asdfghjkl;", "zxcvbnm,./"],
+            KeyboardLayout::Azerty => ["azertyuiop", "qsdfghjklm", "wxcvbn,;:"],
+            KeyboardLayout::Dvorak => ["',.pyfgcrl", "aoeuidhtns", ";qjkxbmwvz"],
+            KeyboardLayout::Colemak => ["qwfpgjluy;", "arstdhneio", "zxcvbkm,./"],
+        }
+    }
+
+    /// Key at the place of `key` on a QWERTY keyboard
+    fn translate(&self, key: char) -> char {
+        for (qwerty, row) in KeyboardLayout::Qwerty.rows().iter().zip(self.rows()) {
+            if let Some(i) = qwerty.chars().position(|c| c == key) {
+                return row.chars().nth(i).unwrap_or(key);
+            }
+        }
+        key
+    }
+}
+
+/// Keys of the player for the actions of the cooking screen.
+///
+/// Only [`crate::input::KeyboardEvent`]s are translated, menus keep the keys of the keyboard.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyBindings {
+    /// Preset of the bindings, `None` once a key was changed
+    pub layout: Option<KeyboardLayout>,
+    keys: HashMap<Binding, char>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings::preset(KeyboardLayout::Qwerty)
+    }
+}
+
+impl KeyBindings {
+    /// Actions at the same place on the keyboard as with the QWERTY bindings
+    pub fn preset(layout: KeyboardLayout) -> Self {
+        KeyBindings {
+            layout: Some(layout),
+            keys: Binding::all().map(|binding| (binding, layout.translate(binding.game_key()))).collect(),
+        }
+    }
+
+    pub fn key(&self, binding: Binding) -> char {
+        self.keys.get(&binding).copied().unwrap_or_else(|| binding.game_key())
+    }
+
+    /// Action bound to a key of the player
+    pub fn binding(&self, key: char) -> Option<Binding> {
+        Binding::all().find(|binding| self.key(*binding) == key)
+    }
+
+    /// Binds `key` to an action, unless another action already uses it: this action is returned instead.
+    pub fn bind(&mut self, binding: Binding, key: char) -> Result<(), Binding> {
+        match self.binding(key) {
+            Some(other) if other != binding => Err(other),
+            Some(_) => Ok(()),
+            None => {
+                self.keys.insert(binding, key);
+                self.layout = None;
+                Ok(())
+            }
+        }
+    }
+
+    /// Key of the game typed with a key of the player.
+    ///
+    /// Keys without an action are kept, unless the game would take them for one.
+    pub fn game_key(&self, key: char) -> Option<char> {
+        match self.binding(key) {
+            Some(binding) => Some(binding.game_key()),
+            None if Binding::all().any(|binding| binding.game_key() == key) => None,
+            None => Some(key),
+        }
+    }
+}
+
+/// Name of a key in the messages of the controls page
+fn key_name(key: char) -> String {
+    match key {
+        ' ' => "SPACE".to_string(),
+        '<' => "BACKSPACE".to_string(),
+        _ => key.to_uppercase().to_string(),
+    }
+}
+
+/// Action waiting for its new key on the controls page
+#[derive(Default)]
+struct Rebinding(Option<Binding>);
+
+#[derive(Component)]
+struct ControlsUi;
+
+#[derive(Component)]
+struct BindingRow;
+
+#[derive(Component)]
+struct LayoutText;
+
+#[derive(Component)]
+struct StatusText;
+
+impl Plugin for BindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KeyBindings>()
+            .init_resource::<Rebinding>()
+            .add_startup_system(load_bindings)
+            .add_system_set(SystemSet::on_enter(GameState::Controls).with_system(setup_controls))
+            .add_system_set(SystemSet::on_update(GameState::Controls).with_system(handle_input))
+            .add_system_set(SystemSet::on_exit(GameState::Controls).with_system(cleanup_controls));
+    }
+}
+
+fn load_bindings(mut bindings: ResMut<KeyBindings>, pkv: Res<PkvStore>) {
+    if let Ok(saved) = pkv.get::<KeyBindings>(KEY_BINDINGS) {
+        *bindings = saved;
+    }
+}
+
+fn layout_name(bindings: &KeyBindings) -> String {
+    format!("layout: {}", bindings.layout.map_or("CUSTOM", |layout| layout.name()))
+}
+
+fn setup_controls(
+    mut commands: Commands,
+    bindings: Res<KeyBindings>,
+    mut release_events: ResMut<Events<KeyboardReleaseEvent>>,
+    textures: Res<TextureAssets>,
+    fonts: Res<FontAssets>,
+) {
+    // The key which opened the page must not select an action
+    release_events.clear();
+
+    spawn_sprite(&mut commands, textures.background.clone(), Vec3::ZERO).insert(ControlsUi);
+    spawn_sprite(&mut commands, textures.counter.clone(), Vec3::new(0., 0., 0.5)).insert(ControlsUi);
+    spawn_text(&mut commands, &fonts, "KEY BINDINGS".to_string(), Vec2::new(16., 160.), Color::WHITE).insert(ControlsUi);
+    spawn_text(&mut commands, &fonts, "tab: next".to_string(), Vec2::new(240., 160.), Color::WHITE).insert(ControlsUi);
+    spawn_text(&mut commands, &fonts, layout_name(&bindings), Vec2::new(136., 160.), Color::WHITE)
+        .insert(LayoutText)
+        .insert(ControlsUi);
+
+    spawn_rows(&mut commands, &bindings, None, &textures, &fonts);
+
+    spawn_text(&mut commands, &fonts, HELP.to_string(), Vec2::new(16., 30.), Color::WHITE)
+        .insert(StatusText)
+        .insert(ControlsUi);
+    spawn_text(&mut commands, &fonts, "escape: back".to_string(), Vec2::new(124., 14.), Color::WHITE).insert(ControlsUi);
+}
+
+/// Three columns of actions, with their key
+fn spawn_rows(
+    commands: &mut Commands,
+    bindings: &KeyBindings,
+    selected: Option<Binding>,
+    textures: &Res<TextureAssets>,
+    fonts: &Res<FontAssets>,
+) {
+    for (i, binding) in Binding::all().enumerate() {
+        let position = Vec2::new(16. + 104. * (i / 6) as f32, 136. - 18. * (i % 6) as f32);
+        let (button, _) = spawn_button(commands, position, bindings.key(binding), textures, fonts, false);
+        commands.entity(button).insert(BindingRow).insert(ControlsUi);
+
+        let color = if selected == Some(binding) { Color::rgb(1., 0.85, 0.3) } else { Color::WHITE };
+        spawn_text(commands, fonts, binding.name(), position + Vec2::new(16., 2.), color)
+            .insert(BindingRow)
+            .insert(ControlsUi);
+    }
+}
+
+/// What the keys of a frame change on the controls page
+#[derive(Default, Debug)]
+struct ControlsChange {
+    status: Option<String>,
+    /// The rows are drawn again when the bindings or the selected action change
+    redraw: bool,
+    save: bool,
+}
+
+/// Applies the keys of a frame to the bindings, `None` when the page is left
+fn read_controls(
+    bindings: &mut KeyBindings,
+    rebinding: &mut Rebinding,
+    escape: bool,
+    tab: bool,
+    keys: impl Iterator<Item = char>,
+) -> Option<ControlsChange> {
+    let mut change = ControlsChange::default();
+
+    if escape {
+        rebinding.0.take()?;
+        change.status = Some(HELP.to_string());
+        change.redraw = true;
+    } else if tab {
+        let layout = bindings.layout.map_or(KeyboardLayout::Qwerty, |layout| layout.next());
+        *bindings = KeyBindings::preset(layout);
+        rebinding.0 = None;
+        change.status = Some(HELP.to_string());
+        change.redraw = true;
+        change.save = true;
+    }
+
+    for key in keys {
+        match rebinding.0 {
+            None => {
+                if let Some(binding) = bindings.binding(key) {
+                    rebinding.0 = Some(binding);
+                    change.status = Some(format!("press the new key of {}", binding.name()));
+                    change.redraw = true;
+                }
+            }
+            Some(binding) => match bindings.bind(binding, key) {
+                Ok(()) => {
+                    rebinding.0 = None;
+                    change.status = Some(HELP.to_string());
+                    change.redraw = true;
+                    change.save = true;
+                }
+                Err(other) => change.status = Some(format!("{} is already the key of {}", key_name(key), other.name())),
+            },
+        }
+    }
+
+    Some(change)
+}
+
+/// Texts and rows of the controls page
+#[derive(SystemParam)]
+struct ControlsPage<'w, 's> {
+    commands: Commands<'w, 's>,
+    rows: Query<'w, 's, Entity, With<BindingRow>>,
+    layout_text: Query<'w, 's, &'static mut Text, (With<LayoutText>, Without<StatusText>)>,
+    status_text: Query<'w, 's, &'static mut Text, (With<StatusText>, Without<LayoutText>)>,
+    textures: Res<'w, TextureAssets>,
+    fonts: Res<'w, FontAssets>,
+}
+
+impl<'w, 's> ControlsPage<'w, 's> {
+    fn show_status(&mut self, status: &str) {
+        for mut text in self.status_text.iter_mut() {
+            text.sections[0].value = status.to_string();
+        }
+    }
+
+    fn redraw(&mut self, bindings: &KeyBindings, selected: Option<Binding>) {
+        for mut text in self.layout_text.iter_mut() {
+            text.sections[0].value = layout_name(bindings);
+        }
+        for entity in self.rows.iter() {
+            self.commands.entity(entity).despawn_recursive();
+        }
+        spawn_rows(&mut self.commands, bindings, selected, &self.textures, &self.fonts);
+    }
+}
+
+fn handle_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut events: EventReader<KeyboardReleaseEvent>,
+    mut state: ResMut<State<GameState>>,
+    mut bindings: ResMut<KeyBindings>,
+    mut rebinding: ResMut<Rebinding>,
+    mut pkv: ResMut<PkvStore>,
+    mut page: ControlsPage,
+) {
+    let escape = keyboard_input.just_pressed(KeyCode::Escape);
+    let tab = keyboard_input.just_pressed(KeyCode::Tab);
+    let keys = events.iter().map(|KeyboardReleaseEvent(key)| *key);
+    let change = match read_controls(&mut bindings, &mut rebinding, escape, tab, keys) {
+        Some(change) => change,
+        None => {
+            state.set(GameState::Settings).unwrap_or_default();
+            return;
+        }
+    };
+
+    if let Some(status) = change.status {
+        page.show_status(&status);
+    }
+
+    if change.save {
+        let _ = pkv.set(KEY_BINDINGS, &*bindings);
+    }
+
+    if change.redraw {
+        page.redraw(&bindings, rebinding.0);
+    }
+}
+
+fn cleanup_controls(
+    mut commands: Commands,
+    mut rebinding: ResMut<Rebinding>,
+    entities: Query<Entity, With<ControlsUi>>,
+) {
+    rebinding.0 = None;
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_cancels_the_rebinding() {
+        let mut bindings = KeyBindings::default();
+        let mut rebinding = Rebinding::default();
+
+        let change = read_controls(&mut bindings, &mut rebinding, false, false, "a".chars()).unwrap();
+        assert_eq!(rebinding.0, Some(Binding::Ingredient(Ingredient::Bacon)));
+        assert!(change.redraw);
+
+        // The rows are drawn again without the selected action
+        let change = read_controls(&mut bindings, &mut rebinding, true, false, "".chars()).unwrap();
+        assert_eq!(rebinding.0, None);
+        assert!(change.redraw);
+        assert!(!change.save);
+        assert_eq!(change.status.as_deref(), Some(HELP));
+
+        // Nothing is waiting for a key anymore, the page is left
+        assert!(read_controls(&mut bindings, &mut rebinding, true, false, "".chars()).is_none());
+    }
+}
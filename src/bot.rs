@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::{GameState, is_headless, Labels};
+use crate::clock::GameClock;
+use crate::cooking::{CurrentBurger, ExpectingOrder, MadnessMode};
+use crate::difficulty::GameMode;
+use crate::input::KeyboardEvent;
+use crate::order::{MenuOnDisplay, Order};
+use crate::rng::Seed;
+use crate::score::Score;
+
+pub struct BotPlugin;
+
+/// Time without input on the title screen before the bot starts a demo
+const ATTRACT_DELAY: f32 = 20.;
+/// Time the game over screen is shown before the bot goes on
+const GAME_OVER_DELAY: f32 = 4.;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+                SystemSet::on_enter(GameState::Cooking)
+                    .before(Labels::LogicSender)
+                    .with_system(reset_bot)
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Cooking)
+                    .before(Labels::LogicSender)
+                    .with_system(bot_play)
+            );
+
+        if is_headless(app) { return; }
+
+        app.add_system_set(
+                SystemSet::on_update(GameState::TitleScreen)
+                    .with_system(start_bot)
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Cooking)
+                    .with_system(stop_demo)
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver)
+                    .with_system(report_survival)
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver)
+                    .with_system(leave_game_over)
+            );
+    }
+}
+
+/// While this resource exists, [`KeyboardEvent`]s are typed by the bot instead of the keyboard.
+///
+/// The bot reads the current order and types it at a steady pace, making mistakes now and then.
+pub struct Bot {
+    pub keys_per_second: f32,
+    /// Probability to type a wrong ingredient
+    pub error_rate: f64,
+    /// In attract mode, the bot plays a demo until a key is pressed.
+    /// Otherwise, it plays game after game and reports how long it survived.
+    pub attract: bool,
+    timer: Timer,
+    rng: StdRng,
+}
+
+impl Bot {
+    pub fn new(keys_per_second: f32, error_rate: f64, attract: bool) -> Self {
+        Bot {
+            keys_per_second,
+            error_rate,
+            attract,
+            timer: Timer::from_seconds(1. / keys_per_second, true),
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+
+    /// Bot used for the title screen demo
+    pub fn demo() -> Self {
+        Bot::new(4., 0.05, true)
+    }
+
+    /// Next key the bot wants to press
+    fn next_key(&mut self, order: &Order, burger: &CurrentBurger, menu: &MenuOnDisplay) -> char {
+        // Start again after a mistake
+        if !order.ingredients.starts_with(&burger.ingredients) {
+            return '<';
+        }
+
+        match order.ingredients.get(burger.ingredients.len()) {
+            Some(expected) => {
+                if self.rng.gen_bool(self.error_rate) {
+                    if let Some(wrong) = menu.ingredients.iter().filter(|&i| i != expected).choose(&mut self.rng) {
+                        return wrong.key();
+                    }
+                }
+                expected.key()
+            }
+            None => ' ',
+        }
+    }
+}
+
+fn reset_bot(
+    seed: Res<Seed>,
+    bot: Option<ResMut<Bot>>,
+) {
+    if let Some(mut bot) = bot {
+        bot.rng = StdRng::seed_from_u64(seed.value);
+        bot.timer.reset();
+    }
+}
+
+fn bot_play(
+    clock: Res<GameClock>,
+    order: Res<Order>,
+    current_burger: Res<CurrentBurger>,
+    menu: Res<MenuOnDisplay>,
+    expecting_order: Res<ExpectingOrder>,
+    bot: Option<ResMut<Bot>>,
+    mut events: EventWriter<KeyboardEvent>,
+) {
+    if let Some(mut bot) = bot {
+        bot.timer.tick(clock.delta());
+        if bot.timer.just_finished() && expecting_order.0 {
+            let key = bot.next_key(&order, &current_burger, &menu);
+            events.send(KeyboardEvent(key));
+        }
+    }
+}
+
+fn start_bot(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut state: ResMut<State<GameState>>,
+    mut idle: Local<f32>,
+    mut input: EventReader<KeyboardEvent>,
+    bot: Option<Res<Bot>>,
+) {
+    if input.iter().count() > 0 {
+        *idle = 0.;
+    }
+    *idle += time.delta_seconds();
+
+    match bot {
+        // A demo is over, stay on the title screen
+        Some(bot) if bot.attract => {
+            commands.remove_resource::<Bot>();
+            *idle = 0.;
+        }
+        // The bot plays game after game
+        Some(_) => { state.set(GameState::Cooking).unwrap_or_default(); }
+        None => {
+            if *idle > ATTRACT_DELAY {
+                *idle = 0.;
+                commands.insert_resource(Bot::demo());
+                commands.insert_resource(MadnessMode(false));
+                commands.insert_resource(GameMode::default());
+                state.set(GameState::Cooking).unwrap_or_default();
+            }
+        }
+    }
+}
+
+fn stop_demo(
+    keys: Res<Input<KeyCode>>,
+    bot: Option<Res<Bot>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if let Some(bot) = bot {
+        if bot.attract && keys.get_just_pressed().next().is_some() {
+            state.set(GameState::TitleScreen).unwrap_or_default();
+        }
+    }
+}
+
+fn report_survival(
+    clock: Res<GameClock>,
+    score: Res<Score>,
+    mode: Res<GameMode>,
+    seed: Res<Seed>,
+    bot: Option<Res<Bot>>,
+) {
+    if let Some(bot) = bot {
+        if bot.attract { return; }
+        info!(
+            "Bot ({} keys/s, {:.0}% errors) survived {:.1}s in {} mode with seed {}, score: {}",
+            bot.keys_per_second,
+            bot.error_rate * 100.,
+            clock.elapsed().as_secs_f32(),
+            mode.name(),
+            seed.value,
+            score.score,
+        );
+    }
+}
+
+fn leave_game_over(
+    time: Res<Time>,
+    mut waited: Local<f32>,
+    bot: Option<Res<Bot>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if bot.is_none() { return; }
+    *waited += time.delta_seconds();
+    if *waited > GAME_OVER_DELAY {
+        *waited = 0.;
+        state.set(GameState::TitleScreen).unwrap_or_default();
+    }
+}
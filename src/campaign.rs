@@ -0,0 +1,374 @@
+use std::marker::PhantomData;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+use crate::{GameState, is_headless, Labels, spawn_sprite, spawn_text};
+use crate::button::spawn_button;
+use crate::cooking::MadnessMode;
+use crate::difficulty::GameMode;
+use crate::ingredients::{Ingredient, Menu};
+use crate::input::KeyboardReleaseEvent;
+use crate::loading::{FontAssets, TextureAssets};
+use crate::replay::ReplayRecorder;
+use crate::score::Score;
+use crate::stats::RunStats;
+
+pub struct CampaignPlugin;
+
+/// [`PkvStore`] key of the [`CampaignProgress`]
+pub const CAMPAIGN_PROGRESS: &str = "campaign";
+
+/// Days are played with the number keys
+const MAX_DAYS: usize = 9;
+
+/// What the player must do to complete a day
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Deserialize)]
+pub enum Goal {
+    Burgers(u32),
+    Score(i64),
+}
+
+impl Goal {
+    pub fn reached(&self, score: &Score, stats: &RunStats) -> bool {
+        match *self {
+            Goal::Burgers(burgers) => stats.burgers >= burgers,
+            Goal::Score(points) => score.score >= points,
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Goal::Burgers(burgers) => format!("serve {} burgers", burgers),
+            Goal::Score(points) => format!("reach {} points", points),
+        }
+    }
+
+    /// Progress shown during the game, like `3/5`
+    fn progress(&self, score: &Score, stats: &RunStats) -> String {
+        match *self {
+            Goal::Burgers(burgers) => format!("{}/{}", stats.burgers.min(burgers), burgers),
+            Goal::Score(points) => format!("{}/{}", score.score.clamp(0, points), points),
+        }
+    }
+}
+
+/// A day in the restaurant
+#[derive(Clone, Debug, Deserialize)]
+pub struct Day {
+    pub goal: Goal,
+    /// Ingredients added to the next days when this one is completed
+    pub unlocks: Vec<Ingredient>,
+}
+
+/// The days of the campaign, as found in a `.campaign.ron` asset next to the menu book
+#[derive(Clone, Debug, Deserialize, TypeUuid)]
+#[uuid = "7bdb9bf2-7244-4be6-9572-3b1fff636bb8"]
+pub struct Campaign {
+    /// Ingredients of the first day, the other ones are unlocked day after day
+    pub first_ingredients: Vec<Ingredient>,
+    pub days: Vec<Day>,
+}
+
+impl Campaign {
+    /// Reads and validates a `.campaign.ron` file
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Campaign> {
+        let campaign = ron::de::from_bytes::<Campaign>(bytes)?;
+        campaign.validate()?;
+        Ok(campaign)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!((1..=MAX_DAYS).contains(&self.days.len()), "the campaign must have 1 to {} days", MAX_DAYS);
+        let ingredients: Vec<Ingredient> = self.first_ingredients
+            .iter()
+            .chain(self.days.iter().flat_map(|day| day.unlocks.iter()))
+            .copied()
+            .collect();
+        for (i, ingredient) in ingredients.iter().enumerate() {
+            anyhow::ensure!(*ingredient != Ingredient::Bread, "bread is implicit and can't be listed");
+            anyhow::ensure!(!ingredients[..i].contains(ingredient), "{:?} is listed twice", ingredient);
+        }
+        for (i, day) in self.days.iter().enumerate() {
+            let positive = match day.goal {
+                Goal::Burgers(burgers) => burgers > 0,
+                Goal::Score(points) => points > 0,
+            };
+            anyhow::ensure!(positive, "the goal of day {} must be positive", i + 1);
+        }
+        Ok(())
+    }
+
+    /// Ingredients served on `day` (0 for the first one)
+    pub fn day_ingredients(&self, day: usize) -> Vec<Ingredient> {
+        self.first_ingredients
+            .iter()
+            .chain(self.days[..day].iter().flat_map(|day| day.unlocks.iter()))
+            .copied()
+            .collect()
+    }
+}
+
+/// Days completed by the player, the next one is the only new day which can be played
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct CampaignProgress {
+    pub completed: usize,
+}
+
+impl CampaignProgress {
+    pub fn load(pkv: &PkvStore) -> CampaignProgress {
+        pkv.get::<CampaignProgress>(CAMPAIGN_PROGRESS).unwrap_or_default()
+    }
+
+    pub fn save(&self, pkv: &mut PkvStore) {
+        let _ = pkv.set(CAMPAIGN_PROGRESS, self);
+    }
+
+    pub fn is_open(&self, day: usize, campaign: &Campaign) -> bool {
+        day < campaign.days.len() && day <= self.completed
+    }
+
+    /// Returns the ingredients unlocked by `day`, none if it was already completed
+    pub fn complete(&mut self, day: usize, campaign: &Campaign) -> Vec<Ingredient> {
+        if day != self.completed || day >= campaign.days.len() { return vec![]; }
+        self.completed += 1;
+        campaign.days[day].unlocks.clone()
+    }
+}
+
+#[derive(Component)]
+struct CampaignUi;
+
+/// Progress towards the goal of the day, under the lives
+#[derive(Component)]
+struct GoalText;
+
+/// Why the last day chosen on the campaign page can't be played
+#[derive(Component)]
+struct DayError;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Cooking)
+                .after(Labels::LogicReceiver)
+                .before(Labels::UI)
+                .with_system(watch_goal),
+        );
+
+        if is_headless(app) { return; }
+
+        app.add_system_set(SystemSet::on_enter(GameState::Cooking).with_system(spawn_goal))
+            .add_system_set(SystemSet::on_update(GameState::Cooking).label(Labels::UI).with_system(update_goal))
+            .add_system_set(SystemSet::on_exit(GameState::Cooking).with_system(cleanup_campaign_ui))
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(record_day))
+            .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(cleanup_campaign_ui))
+            .add_system_set(SystemSet::on_enter(GameState::Campaign).with_system(setup_campaign_page))
+            .add_system_set(SystemSet::on_update(GameState::Campaign).with_system(handle_input))
+            .add_system_set(SystemSet::on_exit(GameState::Campaign).with_system(cleanup_campaign_ui));
+    }
+}
+
+/// Day of the campaign being played, if any
+fn current_day<'a>(mode: &GameMode, campaign: &'a Campaign) -> Option<(usize, &'a Day)> {
+    match mode {
+        GameMode::Campaign { day, .. } => campaign.days.get(*day).map(|played| (*day, played)),
+        _ => None,
+    }
+}
+
+/// Day being played with the score and stats its goal is checked against
+#[derive(SystemParam)]
+struct DayProgress<'w, 's> {
+    mode: Res<'w, GameMode>,
+    campaign: Res<'w, Campaign>,
+    score: Res<'w, Score>,
+    stats: Res<'w, RunStats>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> DayProgress<'w, 's> {
+    fn day(&self) -> Option<(usize, &Day)> {
+        current_day(&self.mode, &self.campaign)
+    }
+
+    fn reached(&self, day: &Day) -> bool {
+        day.goal.reached(&self.score, &self.stats)
+    }
+
+    fn progress(&self, day: &Day) -> String {
+        day.goal.progress(&self.score, &self.stats)
+    }
+}
+
+/// The day ends as soon as its goal is reached
+fn watch_goal(
+    progress: DayProgress,
+    mut state: ResMut<State<GameState>>,
+) {
+    if let Some((_, day)) = progress.day() {
+        if progress.reached(day) {
+            state.set(GameState::GameOver).unwrap_or_default();
+        }
+    }
+}
+
+fn spawn_goal(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    campaign: Res<Campaign>,
+    fonts: Res<FontAssets>,
+) {
+    if current_day(&mode, &campaign).is_none() { return; }
+
+    spawn_text(&mut commands, &fonts, String::new(), Vec2::ZERO, Color::BLACK)
+        .insert(Transform::from_xyz(320. - 56., 132., 5.))
+        .insert(GoalText)
+        .insert(CampaignUi);
+}
+
+fn update_goal(
+    progress: DayProgress,
+    mut texts: Query<&mut Text, With<GoalText>>,
+) {
+    if let Some((_, day)) = progress.day() {
+        for mut text in texts.iter_mut() {
+            text.sections[0].value = progress.progress(day);
+        }
+    }
+}
+
+/// Saves the completed day and tells the player what it unlocked, on top of the bill
+fn record_day(
+    mut commands: Commands,
+    played: DayProgress,
+    recorder: Res<ReplayRecorder>,
+    mut pkv: ResMut<PkvStore>,
+    fonts: Res<FontAssets>,
+) {
+    if !recorder.is_player_game() { return; }
+    let (number, day) = match played.day() {
+        Some(day) => day,
+        None => return,
+    };
+
+    let text = if played.reached(day) {
+        let mut progress = CampaignProgress::load(&pkv);
+        let unlocked = progress.complete(number, &played.campaign);
+        progress.save(&mut pkv);
+        if unlocked.is_empty() {
+            format!("DAY {} COMPLETE!", number + 1)
+        } else {
+            let names: Vec<String> = unlocked.iter().map(|i| i.name().to_uppercase()).collect();
+            format!("DAY {} COMPLETE! NEW: {}", number + 1, names.join(", "))
+        }
+    } else {
+        format!("DAY {} FAILED, {}", number + 1, day.goal.text().to_uppercase())
+    };
+    spawn_text(&mut commands, &fonts, text, Vec2::new(4., 166.), Color::WHITE).insert(CampaignUi);
+}
+
+fn setup_campaign_page(
+    mut commands: Commands,
+    pkv: Res<PkvStore>,
+    campaign: Res<Campaign>,
+    mut release_events: ResMut<Events<KeyboardReleaseEvent>>,
+    textures: Res<TextureAssets>,
+    fonts: Res<FontAssets>,
+) {
+    // The key which opened the page must not start a day
+    release_events.clear();
+
+    let progress = CampaignProgress::load(&pkv);
+
+    spawn_sprite(&mut commands, textures.background.clone(), Vec3::ZERO).insert(CampaignUi);
+    spawn_sprite(&mut commands, textures.counter.clone(), Vec3::new(0., 0., 0.5)).insert(CampaignUi);
+    spawn_text(&mut commands, &fonts, "CAMPAIGN".to_string(), Vec2::new(16., 160.), Color::WHITE).insert(CampaignUi);
+
+    for (i, day) in campaign.days.iter().enumerate() {
+        let y = 136. - 16. * i as f32;
+        if progress.is_open(i, &campaign) {
+            let key = char::from_digit(i as u32 + 1, 10).unwrap();
+            let (button, _) = spawn_button(&mut commands, Vec2::new(16., y), key, &textures, &fonts, false);
+            commands.entity(button).insert(CampaignUi);
+        }
+        let (status, color) = if i < progress.completed {
+            (" (done)", Color::rgb(1., 0.85, 0.3))
+        } else if progress.is_open(i, &campaign) {
+            ("", Color::WHITE)
+        } else {
+            (" (locked)", Color::GRAY)
+        };
+        let text = format!("day {}: {}{}", i + 1, day.goal.text(), status);
+        spawn_text(&mut commands, &fonts, text, Vec2::new(36., y + 2.), color).insert(CampaignUi);
+    }
+
+    let ingredients = campaign.day_ingredients(progress.completed.min(campaign.days.len() - 1));
+    let text = format!("ingredients unlocked: {}/{}", ingredients.len(), Ingredient::ALL.len() - 1);
+    spawn_text(&mut commands, &fonts, text, Vec2::new(16., 32.), Color::WHITE).insert(CampaignUi);
+
+    let (button, _) = spawn_button(&mut commands, Vec2::new(124., 12.), ' ', &textures, &fonts, false);
+    commands.entity(button).insert(CampaignUi);
+    spawn_text(&mut commands, &fonts, "back".to_string(), Vec2::new(140., 14.), Color::WHITE).insert(CampaignUi);
+}
+
+/// Error text of the campaign page
+#[derive(SystemParam)]
+struct CampaignPage<'w, 's> {
+    commands: Commands<'w, 's>,
+    errors: Query<'w, 's, Entity, With<DayError>>,
+    fonts: Res<'w, FontAssets>,
+}
+
+impl<'w, 's> CampaignPage<'w, 's> {
+    fn show_error(&mut self, error: String) {
+        for entity in self.errors.iter() {
+            self.commands.entity(entity).despawn_recursive();
+        }
+        spawn_text(&mut self.commands, &self.fonts, error, Vec2::new(16., 48.), Color::rgb(1., 0.85, 0.3))
+            .insert(DayError)
+            .insert(CampaignUi);
+    }
+}
+
+fn handle_input(
+    pkv: Res<PkvStore>,
+    campaign: Res<Campaign>,
+    menu: Res<Menu>,
+    mut events: EventReader<KeyboardReleaseEvent>,
+    mut state: ResMut<State<GameState>>,
+    mut page: CampaignPage,
+) {
+    for KeyboardReleaseEvent(key) in events.iter() {
+        match *key {
+            ' ' | '<' => { state.set(GameState::TitleScreen).unwrap_or_default(); }
+            '1'..='9' => {
+                let day = key.to_digit(10).unwrap() as usize - 1;
+                if !CampaignProgress::load(&pkv).is_open(day, &campaign) { continue; }
+                let mode = GameMode::campaign(day, campaign.day_ingredients(day));
+                // The menu served may lack the ingredients the day needs
+                if let Err(e) = mode.validate(&menu) {
+                    page.show_error(format!("day {} can't be served with {}: {}", day + 1, menu.name, e));
+                    continue;
+                }
+                page.commands.insert_resource(MadnessMode(false));
+                page.commands.insert_resource(mode);
+                state.set(GameState::Cooking).unwrap_or_default();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn cleanup_campaign_ui(
+    mut commands: Commands,
+    entities: Query<Entity, With<CampaignUi>>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -7,8 +7,8 @@ use bevy_tweening::{Animator, Delay, Sequence};
 
 use crate::{GameState, Labels};
 use crate::loading::TextureAssets;
-use crate::restaurant::ShowIngredientEvent;
-use crate::tween::{tween_position, tween_sprite_opacity, TWEEN_TIME};
+use crate::restaurant::{AddIngredientTimer, ShowIngredientEvent};
+use crate::tween::{tween_position, tween_sprite_opacity};
 
 pub struct ChefPlugin;
 
@@ -60,25 +60,29 @@ fn init_chef(
 fn show_chef(
     mut commands: Commands,
     mut ev_show_ingredient: EventReader<ShowIngredientEvent>,
+    add_ingredient_timer: Res<AddIngredientTimer>,
     chef: Query<Entity, With<ChefUI>>,
 ) {
     if let entity = chef.single() {
+        // The chef hurries when the menu changes quickly, so that they are gone before the next ingredient
+        let walk = add_ingredient_timer.chef_walk_time();
+        let writing = (WRITING_TIME as f32 * add_ingredient_timer.animation_speed()) as u64;
         for &ShowIngredientEvent { replace, position, ingredient, timer } in ev_show_ingredient.iter() {
             if !timer { continue; }
             commands
                 .entity(entity)
                 .insert(Animator::new(Sequence::new([
-                    tween_position(writing_pos(position, 0) + Vec2::new(-64., 0.), writing_pos(position, 0), TOP_POS.z, TWEEN_TIME * 2),
-                    tween_position(writing_pos(position, 0), writing_pos(position, 1), TOP_POS.z, WRITING_TIME),
-                    tween_position(writing_pos(position, 1), writing_pos(position, 2), TOP_POS.z, WRITING_TIME),
-                    tween_position(writing_pos(position, 2), writing_pos(position, 3), TOP_POS.z, WRITING_TIME),
-                    tween_position(writing_pos(position, 3), writing_pos(position, 4), TOP_POS.z, WRITING_TIME),
-                    tween_position(writing_pos(position, 4), writing_pos(position, 4) + Vec2::new(-64., 0.), TOP_POS.z, TWEEN_TIME * 2),
+                    tween_position(writing_pos(position, 0) + Vec2::new(-64., 0.), writing_pos(position, 0), TOP_POS.z, walk),
+                    tween_position(writing_pos(position, 0), writing_pos(position, 1), TOP_POS.z, writing),
+                    tween_position(writing_pos(position, 1), writing_pos(position, 2), TOP_POS.z, writing),
+                    tween_position(writing_pos(position, 2), writing_pos(position, 3), TOP_POS.z, writing),
+                    tween_position(writing_pos(position, 3), writing_pos(position, 4), TOP_POS.z, writing),
+                    tween_position(writing_pos(position, 4), writing_pos(position, 4) + Vec2::new(-64., 0.), TOP_POS.z, walk),
                 ])))
                 .insert(Animator::new(
-                    tween_sprite_opacity(TWEEN_TIME * 2, true).then(
-                        Delay::new(Duration::from_millis(WRITING_TIME * 4)).then(
-                            tween_sprite_opacity(TWEEN_TIME * 2, false)))
+                    tween_sprite_opacity(walk, true).then(
+                        Delay::new(Duration::from_millis(writing * 4)).then(
+                            tween_sprite_opacity(walk, false)))
                 ));
         }
     }
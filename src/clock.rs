@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{GameState, Labels};
+use crate::replay::Playback;
+
+pub struct ClockPlugin;
+
+/// A frame longer than this only advances the [`GameClock`] by this much
+pub const MAX_FRAME_TIME: Duration = Duration::from_millis(100);
+
+/// Time of the current game, used for the orders, the customers' patience, the menu and the replays.
+///
+/// Unlike [`Time`], it only advances while cooking: pauses don't count,
+/// and a frame hitch can't take more than [`MAX_FRAME_TIME`] from the player.
+#[derive(Default)]
+pub struct GameClock {
+    elapsed: Duration,
+    delta: Duration,
+    frame: u32,
+}
+
+impl GameClock {
+    /// Time since the start of the game
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Time added during this frame
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Frames since the start of the game, the first one is 0
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        self.delta = delta.min(MAX_FRAME_TIME);
+        self.elapsed += self.delta;
+        self.frame += 1;
+    }
+}
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameClock>()
+            .add_system_to_stage(CoreStage::PreUpdate, tick_clock)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Cooking)
+                    .before(Labels::LogicSender)
+                    .with_system(reset_clock),
+            );
+    }
+}
+
+fn reset_clock(mut clock: ResMut<GameClock>) {
+    *clock = GameClock::default();
+}
+
+/// Runs before the state is updated, so only frames which start while cooking count.
+/// A replay is played with the durations of the frames it was recorded with.
+fn tick_clock(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    playback: Option<Res<Playback>>,
+    mut clock: ResMut<GameClock>,
+) {
+    if *state.current() == GameState::Cooking {
+        let recorded = playback.and_then(|playback| playback.frame_delta(clock.frame + 1));
+        clock.tick(recorded.unwrap_or_else(|| time.delta()));
+    } else {
+        clock.delta = Duration::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_hitch_is_capped() {
+        let mut clock = GameClock::default();
+        clock.tick(Duration::from_secs(3));
+        assert_eq!(clock.elapsed(), MAX_FRAME_TIME);
+        assert_eq!(clock.frame(), 1);
+    }
+}
@@ -1,19 +1,23 @@
+use std::marker::PhantomData;
 use std::ops::Add;
 use std::time::Duration;
 
+use bevy::ecs::system::SystemParam;
 use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
 use bevy::sprite::Anchor;
 use bevy_tweening::{Animator, Delay, Sequence};
-use rand::random;
+use rand::Rng;
 
-use crate::{GameState, Labels, tween};
+use crate::{GameState, is_headless, Labels, tween};
 use crate::audio::{BGM, PlayBgmEvent, PlaySfxEvent, SFX};
 use crate::customer::CallNewCustomer;
-use crate::ingredients::Ingredient;
+use crate::difficulty::GameMode;
+use crate::ingredients::{edit_distance, Ingredient};
 use crate::input::KeyboardEvent;
 use crate::loading::{FontAssets, TextureAssets};
 use crate::order::{BurgerFinishedEvent, MenuOnDisplay, Order};
+use crate::rng::GameRng;
 use crate::score::Score;
 use crate::tween::{tween_position, tween_text_opacity};
 
@@ -43,13 +47,24 @@ impl Plugin for CookingPlugin {
             )
             .add_system_set(
                 SystemSet::on_update(GameState::Cooking)
-                    .label(Labels::UI)
-                    .after(Labels::LogicReceiver)
+                    .label(Labels::LogicReceiver)
+                    .after(Labels::LogicSender)
                     .with_system(add_ingredient)
-                    .with_system(display_streak_or_miss)
-                    .with_system(animate_burger),
+                    .with_system(clear_burger),
             )
-            .add_system_set(SystemSet::on_exit(GameState::Cooking).with_system(clean_cooking_ui));
+            .add_system_set(SystemSet::on_exit(GameState::Cooking).with_system(clean_cooking_ui))
+            .add_event::<IngredientAddedEvent>();
+
+        if is_headless(app) { return; }
+
+        app.add_system_set(
+            SystemSet::on_update(GameState::Cooking)
+                .label(Labels::UI)
+                .after(Labels::LogicReceiver)
+                .with_system(show_added_ingredient)
+                .with_system(display_streak_or_miss)
+                .with_system(animate_burger),
+        );
     }
 }
 
@@ -64,6 +79,13 @@ pub struct CurrentBurger {
 #[derive(Component)]
 struct CurrentBurgerIngredient;
 
+/// Event sent when an ingredient has been put on the burger
+pub struct IngredientAddedEvent {
+    pub ingredient: Ingredient,
+    /// Index of the ingredient in the burger
+    pub position: usize,
+}
+
 pub struct ExpectingOrder(pub bool);
 
 pub struct MadnessMode(pub bool);
@@ -86,9 +108,8 @@ fn add_ingredient(
     mut input: EventReader<KeyboardEvent>,
     mut current_burger: ResMut<CurrentBurger>,
     mut ev_sfx: EventWriter<PlaySfxEvent>,
+    mut ev_ingredient_added: EventWriter<IngredientAddedEvent>,
     menu: Res<MenuOnDisplay>,
-    textures: Res<TextureAssets>,
-    mut commands: Commands,
 ) {
     for KeyboardEvent(key) in input.iter() {
         if let Some(ingredient) = Ingredient::from_key(&key) {
@@ -100,47 +121,58 @@ fn add_ingredient(
             // Play a sound
             ev_sfx.send(PlaySfxEvent(ingredient.sfx()));
 
-            // Display the added ingredient
-            let ingredients_nb = current_burger.ingredients.len();
-            let ingredient_pos_starting = Vec2::new(
-                116. + if ingredients_nb % 2 == 0 { -4. } else { 4. },
-                14. + 8. * ingredients_nb as f32,
-            );
-            let ingredient_pos = Vec2::new(116., 14. + 8. * ingredients_nb as f32);
-            let ingredient_z = 1. + ingredients_nb as f32 / 20.;
-            commands
-                .spawn_bundle(SpriteSheetBundle {
-                    texture_atlas: textures.ingredients.clone(),
-                    sprite: TextureAtlasSprite {
-                        index: ingredient.atlas_key(ingredients_nb == 0),
-                        anchor: Anchor::BottomLeft,
-                        color: Color::rgba(1., 1., 1., 0.),
-                        ..Default::default()
-                    },
-                    transform: Transform::from_translation(
-                        ingredient_pos_starting.extend(ingredient_z),
-                    ),
-                    ..Default::default()
-                })
-                .insert(Animator::new(tween::tween_opacity(
-                    tween::TWEEN_TIME / 2,
-                    true,
-                )))
-                .insert(Animator::new(tween::tween_position(
-                    ingredient_pos_starting,
-                    ingredient_pos,
-                    ingredient_z,
-                    tween::TWEEN_TIME,
-                )))
-                .insert(CurrentBurgerIngredient)
-                .insert(CookingUI);
-
             // Add ingredient to the current burger
+            ev_ingredient_added.send(IngredientAddedEvent {
+                ingredient,
+                position: current_burger.ingredients.len(),
+            });
             current_burger.ingredients.push(ingredient.clone());
         }
     }
 }
 
+fn show_added_ingredient(
+    mut ev_ingredient_added: EventReader<IngredientAddedEvent>,
+    textures: Res<TextureAssets>,
+    mut commands: Commands,
+) {
+    for &IngredientAddedEvent { ingredient, position: ingredients_nb } in ev_ingredient_added.iter() {
+        // Display the added ingredient
+        let ingredient_pos_starting = Vec2::new(
+            116. + if ingredients_nb % 2 == 0 { -4. } else { 4. },
+            14. + 8. * ingredients_nb as f32,
+        );
+        let ingredient_pos = Vec2::new(116., 14. + 8. * ingredients_nb as f32);
+        let ingredient_z = 1. + ingredients_nb as f32 / 20.;
+        commands
+            .spawn_bundle(SpriteSheetBundle {
+                texture_atlas: textures.ingredients.clone(),
+                sprite: TextureAtlasSprite {
+                    index: ingredient.atlas_key(ingredients_nb == 0),
+                    anchor: Anchor::BottomLeft,
+                    color: Color::rgba(1., 1., 1., 0.),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(
+                    ingredient_pos_starting.extend(ingredient_z),
+                ),
+                ..Default::default()
+            })
+            .insert(Animator::new(tween::tween_opacity(
+                tween::TWEEN_TIME / 2,
+                true,
+            )))
+            .insert(Animator::new(tween::tween_position(
+                ingredient_pos_starting,
+                ingredient_pos,
+                ingredient_z,
+                tween::TWEEN_TIME,
+            )))
+            .insert(CurrentBurgerIngredient)
+            .insert(CookingUI);
+    }
+}
+
 fn delete_current(
     mut input: EventReader<KeyboardEvent>,
     ingredients: Query<(Entity, &Transform), With<CurrentBurgerIngredient>>,
@@ -169,13 +201,64 @@ fn delete_current(
     }
 }
 
+/// Compares the burger being sent with the order
+#[derive(SystemParam)]
+struct BurgerJudge<'w, 's> {
+    order: Res<'w, Order>,
+    current_burger: Res<'w, CurrentBurger>,
+    mode: Res<'w, GameMode>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> BurgerJudge<'w, 's> {
+    fn judge(&self) -> BurgerFinishedEvent {
+        let burger = &self.current_burger.ingredients;
+        let correct = *burger == self.order.ingredients;
+        let mistakes = edit_distance(burger, &self.order.ingredients);
+        let partial = !correct && mistakes <= self.mode.difficulty().tolerated_mistakes;
+        let longest = burger.len().max(self.order.ingredients.len());
+        BurgerFinishedEvent {
+            correct,
+            size: burger.len(),
+            out_of_time: false,
+            accuracy: 1. - mistakes as f64 / longest as f64,
+            partial,
+        }
+    }
+}
+
+/// Sounds of the kitchen and of the customer when a burger is sent
+#[derive(SystemParam)]
+struct OrderSfx<'w, 's> {
+    ev_sfx: EventWriter<'w, 's, PlaySfxEvent>,
+    rng: ResMut<'w, GameRng>,
+}
+
+impl<'w, 's> OrderSfx<'w, 's> {
+    fn play(&mut self, correct: bool, partial: bool) {
+        match (correct, partial) {
+            (true, _) => {
+                self.ev_sfx.send(PlaySfxEvent(SFX::CorrectOrder));
+                if self.rng.customers.gen_bool(0.3) { self.ev_sfx.send(PlaySfxEvent(SFX::CustomerHappy)); }
+            }
+            (false, true) => {
+                self.ev_sfx.send(PlaySfxEvent(SFX::CorrectOrder));
+            }
+            (false, false) => {
+                self.ev_sfx.send(PlaySfxEvent(SFX::IncorrectOrder));
+                self.ev_sfx.send(PlaySfxEvent(SFX::CustomerSad));
+            }
+        }
+    }
+}
+
 fn send_order(
-    order: Res<Order>,
+    judge: BurgerJudge,
     expecting_order: Res<ExpectingOrder>,
-    current_burger: Res<CurrentBurger>,
     mut input: EventReader<KeyboardEvent>,
     mut ev_send_burger: EventWriter<BurgerFinishedEvent>,
-    mut ev_sfx: EventWriter<PlaySfxEvent>,
+    mut sfx: OrderSfx,
     mut commands: Commands,
     mut order_nb: ResMut<OrderNumber>,
 ) {
@@ -185,25 +268,12 @@ fn send_order(
                 return;
             }
 
-            if current_burger.ingredients.len() > 0 {
+            if !judge.current_burger.ingredients.is_empty() {
                 commands.insert_resource(ExpectingOrder(false));
                 order_nb.amount += &1;
-                let correct = current_burger.ingredients == order.ingredients;
-                match correct {
-                    true => {
-                        ev_sfx.send(PlaySfxEvent(SFX::CorrectOrder));
-                        if random::<f32>() < 0.3 { ev_sfx.send(PlaySfxEvent(SFX::CustomerHappy)); }
-                    }
-                    false => {
-                        ev_sfx.send(PlaySfxEvent(SFX::IncorrectOrder));
-                        ev_sfx.send(PlaySfxEvent(SFX::CustomerSad));
-                    }
-                }
-                ev_send_burger.send(BurgerFinishedEvent {
-                    correct,
-                    size: current_burger.ingredients.len(),
-                    out_of_time: false
-                });
+                let burger = judge.judge();
+                sfx.play(burger.correct, burger.partial);
+                ev_send_burger.send(burger);
             } else {
                 // TODO: Visual error "can't send an empty order"
             }
@@ -211,13 +281,21 @@ fn send_order(
     }
 }
 
+fn clear_burger(
+    mut ev_burger_finished: EventReader<BurgerFinishedEvent>,
+    mut current_burger: ResMut<CurrentBurger>,
+) {
+    for _ in ev_burger_finished.iter() {
+        current_burger.ingredients.clear();
+    }
+}
+
 fn animate_burger(
     mut commands: Commands,
     mut ev_burger_finished: EventReader<BurgerFinishedEvent>,
-    mut current_burger: ResMut<CurrentBurger>,
     ingredients: Query<(Entity, &Transform), With<CurrentBurgerIngredient>>,
 ) {
-    for BurgerFinishedEvent { correct, size, out_of_time } in ev_burger_finished.iter() {
+    for &BurgerFinishedEvent { correct, partial, .. } in ev_burger_finished.iter() {
         for (entity, transform) in ingredients.iter() {
             let ingredient_position = transform.translation.xy();
             commands
@@ -228,13 +306,12 @@ fn animate_burger(
                             .with_completed_event(tween::EV_DELETE),
                     ),
                 ))
-                .insert(Animator::new(match correct {
+                .insert(Animator::new(match correct || partial {
                     true => win_sequence(ingredient_position, transform.translation.z),
                     false => lose_sequence(ingredient_position, transform.translation.z),
                 }))
                 .remove::<CurrentBurgerIngredient>();
         }
-        current_burger.ingredients.clear();
         break;
     }
     ev_burger_finished.clear();
@@ -290,13 +367,15 @@ fn display_streak_or_miss(
     fonts: Res<FontAssets>,
     mut commands: Commands,
 ) {
-    for &BurgerFinishedEvent { correct, size, out_of_time } in ev_send_burger.iter() {
+    for &BurgerFinishedEvent { correct, size, out_of_time, partial, .. } in ev_send_burger.iter() {
         let text = if correct {
             if score.streak > 1 {
                 format!("{} CHAIN!", score.streak)
             } else {
                 continue;
             }
+        } else if partial {
+            "ALMOST!".to_string()
         } else {
             if out_of_time {
                 "OUT OF TIME!".to_string()
@@ -1,23 +1,22 @@
-use std::cmp::max;
 use std::ops::Add;
 use std::time::Duration;
 
 use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
 use bevy::sprite::{Anchor, MaterialMesh2dBundle};
-use bevy_pkv::PkvStore;
-use bevy_tweening::{Animator, EaseMethod, Tracks, Tween, TweenCompleted, TweeningType};
-use bevy_tweening::lens::{TransformPositionLens, TransformScaleLens};
-use rand::{Rng, thread_rng};
+use bevy_tweening::Animator;
+use rand::Rng;
 
-use crate::{GameState, Labels, spawn_sprite, tween};
+use crate::{GameState, is_headless, Labels, spawn_sprite, tween};
 use crate::audio::{PlaySfxEvent, SFX};
-use crate::cooking::{CurrentBurger, MadnessMode, OrderNumber};
+use crate::clock::GameClock;
+use crate::cooking::{CurrentBurger, OrderNumber};
+use crate::difficulty::GameMode;
 use crate::loading::TextureAssets;
 use crate::order::{BurgerFinishedEvent, Order};
 use crate::restaurant::ShowOrderEvent;
-use crate::score::{EXTRA_TIME_PER_BURGER, Score, TIME_PER_INGREDIENT};
-use crate::tween::{EV_CUSTOMER_EXITED, EV_CUSTOMER_WAITING_TIME_ELAPSED};
+use crate::rng::GameRng;
+use crate::score::Score;
 
 pub struct CustomerPlugin;
 
@@ -30,44 +29,115 @@ struct CurrentCustomer;
 #[derive(Component)]
 struct CustomerTimer;
 
+#[derive(Component)]
+struct WaitingBar {
+    start_position: Vec3,
+}
+
 pub struct CallNewCustomer;
 
+/// State of the current customer, the sprites and the waiting bar only reflect it
+pub struct Customer {
+    /// Runs while the customer waits for their burger
+    pub patience: Timer,
+    /// Runs while the customer leaves the restaurant
+    pub exit: Timer,
+}
+
+impl Default for Customer {
+    fn default() -> Self {
+        Customer {
+            patience: stopped_timer(),
+            exit: stopped_timer(),
+        }
+    }
+}
+
+fn stopped_timer() -> Timer {
+    let mut timer = Timer::default();
+    timer.pause();
+    timer
+}
+
+const WAITING_BAR_SIZE: f32 = 48.;
+
 impl Plugin for CustomerPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<Customer>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Cooking)
+                    .before(Labels::LogicSender)
+                    .with_system(reset_customer),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Cooking)
+                    .label(Labels::LogicReceiver)
+                    .after(Labels::LogicSender)
+                    .with_system(customer_wait)
+                    .with_system(customer_leave)
+                    .with_system(watch_customer_exited)
+                    .with_system(watch_customer_waiting_time),
+            )
+            .add_event::<CallNewCustomer>();
+
+        if is_headless(app) { return; }
+
         app.add_system_set(
             SystemSet::on_update(GameState::Cooking)
                 .label(Labels::UI)
                 .after(Labels::LogicReceiver)
                 .with_system(create_customer_waiting_bars)
+                .with_system(update_waiting_bars)
                 .with_system(customer_enter)
-                .with_system(customer_exit)
-                .with_system(watch_customer_exited)
-                .with_system(watch_customer_waiting_time),
+                .with_system(customer_exit),
         )
-        .add_system_set(SystemSet::on_exit(GameState::Cooking).with_system(clean_customers))
-        .add_event::<CallNewCustomer>();
+        .add_system_set(SystemSet::on_exit(GameState::Cooking).with_system(clean_customers));
     }
 }
 
-fn create_customer_waiting_bars(
+fn reset_customer(mut customer: ResMut<Customer>) {
+    *customer = Customer::default();
+}
+
+fn customer_wait(
     order: Res<Order>,
+    order_nb: Res<OrderNumber>,
+    mode: Res<GameMode>,
+    mut customer: ResMut<Customer>,
+    mut ev_show_order: EventReader<ShowOrderEvent>,
+) {
+    for _ in ev_show_order.iter() {
+        // The patience timer stays stopped
+        if mode.is_zen() { continue; }
+        let duration = mode.difficulty().waiting_time(order.ingredients.len(), order_nb.amount);
+        customer.patience = Timer::new(duration, false);
+    }
+}
+
+fn customer_leave(
+    mut customer: ResMut<Customer>,
+    mut ev_burger: EventReader<BurgerFinishedEvent>,
+) {
+    for _ in ev_burger.iter() {
+        customer.patience.pause();
+        // The customer is gone when their exit animation is over
+        customer.exit = Timer::new(Duration::from_millis(tween::TWEEN_TIME), false);
+    }
+}
+
+fn create_customer_waiting_bars(
     mut commands: Commands,
     mut ev_show_order: EventReader<ShowOrderEvent>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    order_nb: Res<OrderNumber>,
     textures: Res<TextureAssets>,
-    madness: Res<MadnessMode>,
+    mode: Res<GameMode>,
 ) {
     // Create customer timers
     for _ in ev_show_order.iter() {
-        let ingredient_t = if madness.0 { TIME_PER_INGREDIENT - 1. } else { TIME_PER_INGREDIENT };
-        let duration = Duration::from_secs_f64(
-            EXTRA_TIME_PER_BURGER + order.ingredients.len() as f64 * if order_nb.amount < 50 { ingredient_t / (1.0 + order_nb.amount as f64 / 10.0) } else { ingredient_t / 6.0 }
-        );
+        if mode.is_zen() { continue; }
 
         let start_position = Vec3::new(260. + 24., 109., 2.);
-        let x_size = 48.;
 
         spawn_sprite(&mut commands, textures.heart.clone(), Vec3::new(start_position.x - 28., start_position.y - 3., 3.))
             .insert(CustomerTimer)
@@ -79,32 +149,24 @@ fn create_customer_waiting_bars(
                     .add(shape::Quad::new(Vec2::new(1., 4.)).into())
                     .into(),
                 material: materials.add(ColorMaterial::from(Color::WHITE)),
-                transform: Transform::from_translation(start_position),
+                transform: Transform::from_translation(start_position)
+                    .with_scale(Vec3::new(WAITING_BAR_SIZE, 1., 1.)),
                 ..default()
             })
             .insert(CustomerUI)
             .insert(CustomerTimer)
-            .insert(Animator::new(Tracks::new(vec![
-                Tween::new(
-                    EaseMethod::Linear,
-                    TweeningType::Once,
-                    duration,
-                    TransformScaleLens {
-                        start: Vec3::new(x_size, 1., 1.),
-                        end: Vec3::new(1., 1., 1.),
-                    },
-                ),
-                Tween::new(
-                    EaseMethod::Linear,
-                    TweeningType::Once,
-                    duration,
-                    TransformPositionLens {
-                        start: start_position,
-                        end: start_position + Vec3::new(x_size / -2., 0., 0.),
-                    },
-                )
-                .with_completed_event(EV_CUSTOMER_WAITING_TIME_ELAPSED),
-            ])));
+            .insert(WaitingBar { start_position });
+    }
+}
+
+fn update_waiting_bars(
+    customer: Res<Customer>,
+    mut bars: Query<(&mut Transform, &WaitingBar)>,
+) {
+    let left = customer.patience.percent_left();
+    for (mut transform, bar) in bars.iter_mut() {
+        transform.scale.x = 1. + (WAITING_BAR_SIZE - 1.) * left;
+        transform.translation.x = bar.start_position.x - WAITING_BAR_SIZE / 2. * (1. - left);
     }
 }
 
@@ -115,6 +177,7 @@ fn customer_pos() -> Vec3 {
 fn customer_enter(
     mut commands: Commands,
     mut ev_call_new_customer: EventReader<CallNewCustomer>,
+    mut rng: ResMut<GameRng>,
     textures: Res<TextureAssets>,
 ) {
     for CallNewCustomer in ev_call_new_customer.iter() {
@@ -124,7 +187,7 @@ fn customer_enter(
             .spawn_bundle(SpriteSheetBundle {
                 texture_atlas: textures.characters.clone(),
                 sprite: TextureAtlasSprite {
-                    index: thread_rng().gen_range(0..4),
+                    index: rng.customers.gen_range(0..4),
                     anchor: Anchor::BottomLeft,
                     color: Color::rgba(1., 1., 1., 0.),
                     ..Default::default()
@@ -161,8 +224,7 @@ fn customer_exit(
                 .entity(current_customer)
                 .insert(Animator::new(
                     tween::tween_opacity(tween::TWEEN_TIME, false)
-                        .with_completed_event(tween::EV_DELETE)
-                        .with_completed_event(tween::EV_CUSTOMER_EXITED),
+                        .with_completed_event(tween::EV_DELETE),
                 ))
                 .insert(Animator::new(tween::tween_position(
                     customer_pos.xy(),
@@ -180,46 +242,41 @@ fn customer_exit(
 }
 
 fn watch_customer_exited(
+    clock: Res<GameClock>,
     score: Res<Score>,
+    mode: Res<GameMode>,
+    mut customer: ResMut<Customer>,
     mut state: ResMut<State<GameState>>,
-    mut ev_tween_finished: EventReader<TweenCompleted>,
     mut ev_call_new_customer: EventWriter<CallNewCustomer>,
-    madness: Res<MadnessMode>,
-    mut pkv: ResMut<PkvStore>,
 ) {
-    for ev in ev_tween_finished.iter() {
-        if ev.user_data == EV_CUSTOMER_EXITED {
-            if score.lives > 0 {
-                ev_call_new_customer.send(CallNewCustomer);
-            } else {
-                let mode = if madness.0 { "madness" } else { "classic" };
-                // Save score
-                let old_score = if let Ok(s) = pkv.get::<String>(mode) {
-                    s.parse::<i64>().unwrap_or(0)
-                } else { 0 };
-                let _ = pkv.set_string(mode, &*max(score.score, old_score).to_string());
-                state.set(GameState::GameOver).unwrap_or_default();
-            }
+    customer.exit.tick(clock.delta());
+    if customer.exit.just_finished() {
+        if score.lives > 0 || !mode.has_lives() {
+            ev_call_new_customer.send(CallNewCustomer);
+        } else {
+            state.set(GameState::GameOver).unwrap_or_default();
         }
     }
 }
 
 fn watch_customer_waiting_time(
+    clock: Res<GameClock>,
     current_burger: Res<CurrentBurger>,
-    mut ev_tween_finished: EventReader<TweenCompleted>,
+    mut customer: ResMut<Customer>,
     mut ev_burger_completed: EventWriter<BurgerFinishedEvent>,
     mut sfx: EventWriter<PlaySfxEvent>,
 ) {
-    for ev in ev_tween_finished.iter() {
-        if ev.user_data == EV_CUSTOMER_WAITING_TIME_ELAPSED {
-            sfx.send(PlaySfxEvent(SFX::IncorrectOrder));
-            sfx.send(PlaySfxEvent(SFX::CustomerSad));
-            ev_burger_completed.send(BurgerFinishedEvent {
-                correct: false,
-                size: current_burger.ingredients.len(),
-                out_of_time: true,
-            })
-        }
+    customer.patience.tick(clock.delta());
+    if customer.patience.just_finished() {
+        sfx.send(PlaySfxEvent(SFX::IncorrectOrder));
+        sfx.send(PlaySfxEvent(SFX::CustomerSad));
+        ev_burger_completed.send(BurgerFinishedEvent {
+            correct: false,
+            size: current_burger.ingredients.len(),
+            out_of_time: true,
+            accuracy: 0.,
+            partial: false,
+        })
     }
 }
 
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy_pkv::PkvStore;
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{GameState, spawn_sprite, spawn_text};
+use crate::button::spawn_button;
+use crate::cooking::MadnessMode;
+use crate::difficulty::GameMode;
+use crate::input::KeyboardReleaseEvent;
+use crate::loading::{FontAssets, TextureAssets};
+use crate::replay::ReplayRecorder;
+use crate::rng::{MAX_SEED, Seed};
+use crate::score::Score;
+use crate::stats::RunStats;
+
+pub struct DailyPlugin;
+
+/// [`PkvStore`] key of the [`DailyHistory`]
+pub const DAILY_HISTORY: &str = "daily";
+/// Days shown on the calendar, four weeks ending today
+const CALENDAR_DAYS: i64 = 28;
+
+/// Result of the first daily challenge of a day, recorded as soon as it starts
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub score: i64,
+    pub burgers: u32,
+}
+
+/// Results of the daily challenges, by date (`YYYY-MM-DD`)
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct DailyHistory {
+    pub results: BTreeMap<String, DailyResult>,
+}
+
+impl DailyHistory {
+    pub fn load(pkv: &PkvStore) -> DailyHistory {
+        pkv.get::<DailyHistory>(DAILY_HISTORY).unwrap_or_default()
+    }
+
+    pub fn save(&self, pkv: &mut PkvStore) {
+        let _ = pkv.set(DAILY_HISTORY, self);
+    }
+
+    /// Records the start of the challenge of `date` without any point, returns `false` if the day was already played.
+    ///
+    /// Only the first attempt of a day counts, even when it is quit or the game is closed before its end.
+    pub fn start(&mut self, date: &str) -> bool {
+        if self.results.contains_key(date) { return false; }
+        self.results.insert(date.to_string(), DailyResult { score: 0, burgers: 0 });
+        true
+    }
+
+    /// Records the result of the first attempt of `date`
+    pub fn finish(&mut self, date: &str, result: DailyResult) {
+        self.results.insert(date.to_string(), result);
+    }
+
+    pub fn result(&self, date: &str) -> Option<&DailyResult> {
+        self.results.get(date)
+    }
+}
+
+/// Seed of the daily challenge of `date`: everyone playing on the same day gets the same menus and orders.
+///
+/// The date is hashed (64-bit FNV-1a, which doesn't change between platforms and versions of Rust),
+/// so that the seeds of two days in a row are far apart.
+pub fn daily_seed(date: &str) -> u64 {
+    let hash = date.bytes().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    hash % MAX_SEED
+}
+
+/// Today's date in UTC, `YYYY-MM-DD`: the challenge changes at the same time for everyone
+pub fn utc_today() -> String {
+    chrono::Utc::now().format("%Y-%m-%d").to_string()
+}
+
+#[derive(Component)]
+struct DailyUi;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(record_daily))
+            .add_system_set(SystemSet::on_enter(GameState::Daily).with_system(setup_daily_page))
+            .add_system_set(SystemSet::on_update(GameState::Daily).with_system(handle_input))
+            .add_system_set(SystemSet::on_exit(GameState::Daily).with_system(cleanup_daily_page));
+    }
+}
+
+fn record_daily(
+    score: Res<Score>,
+    stats: Res<RunStats>,
+    mode: Res<GameMode>,
+    recorder: Res<ReplayRecorder>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    if !recorder.is_player_game() { return; }
+    // The game may end after midnight, it is recorded under the date it started
+    if let GameMode::Daily { date, recorded: true, .. } = &*mode {
+        let mut history = DailyHistory::load(&pkv);
+        history.finish(date, DailyResult { score: score.score, burgers: stats.burgers });
+        history.save(&mut pkv);
+    }
+}
+
+fn setup_daily_page(
+    mut commands: Commands,
+    pkv: Res<PkvStore>,
+    mut release_events: ResMut<Events<KeyboardReleaseEvent>>,
+    textures: Res<TextureAssets>,
+    fonts: Res<FontAssets>,
+) {
+    // The key which opened the page must not start the game
+    release_events.clear();
+
+    let history = DailyHistory::load(&pkv);
+    let today = utc_today();
+
+    spawn_sprite(&mut commands, textures.background.clone(), Vec3::ZERO).insert(DailyUi);
+    spawn_sprite(&mut commands, textures.counter.clone(), Vec3::new(0., 0., 0.5)).insert(DailyUi);
+    spawn_text(&mut commands, &fonts, format!("DAILY CHALLENGE {}", today), Vec2::new(16., 160.), Color::WHITE).insert(DailyUi);
+
+    // Calendar of the last weeks, today in the bottom right corner
+    if let Ok(date) = NaiveDate::parse_from_str(&today, "%Y-%m-%d") {
+        for i in 0..CALENDAR_DAYS {
+            let day = date - Duration::days(CALENDAR_DAYS - 1 - i);
+            let key = day.format("%Y-%m-%d").to_string();
+            let position = Vec2::new(16. + 42. * (i % 7) as f32, 136. - 26. * (i / 7) as f32);
+            let color = if key == today { Color::rgb(1., 0.85, 0.3) } else { Color::WHITE };
+            spawn_text(&mut commands, &fonts, day.format("%d").to_string(), position, Color::GRAY).insert(DailyUi);
+            let score = history.result(&key).map_or("-".to_string(), |result| result.score.to_string());
+            spawn_text(&mut commands, &fonts, score, position - Vec2::new(0., 10.), color).insert(DailyUi);
+        }
+    }
+
+    let status = match history.result(&today) {
+        Some(result) => format!("today: {} points with {} burgers, come back tomorrow!", result.score, result.burgers),
+        None => "only the first game of the day counts".to_string(),
+    };
+    spawn_text(&mut commands, &fonts, status, Vec2::new(16., 32.), Color::WHITE).insert(DailyUi);
+
+    let (button, _) = spawn_button(&mut commands, Vec2::new(16., 12.), 'y', &textures, &fonts, false);
+    commands.entity(button).insert(DailyUi);
+    spawn_text(&mut commands, &fonts, "play".to_string(), Vec2::new(32., 14.), Color::WHITE).insert(DailyUi);
+
+    let (button, _) = spawn_button(&mut commands, Vec2::new(124., 12.), ' ', &textures, &fonts, false);
+    commands.entity(button).insert(DailyUi);
+    spawn_text(&mut commands, &fonts, "back".to_string(), Vec2::new(140., 14.), Color::WHITE).insert(DailyUi);
+}
+
+fn handle_input(
+    mut commands: Commands,
+    mut pkv: ResMut<PkvStore>,
+    mut events: EventReader<KeyboardReleaseEvent>,
+    mut state: ResMut<State<GameState>>,
+) {
+    for KeyboardReleaseEvent(key) in events.iter() {
+        match *key {
+            ' ' | '<' => { state.set(GameState::TitleScreen).unwrap_or_default(); }
+            'y' => {
+                let date = utc_today();
+                let mut history = DailyHistory::load(&pkv);
+                let recorded = history.start(&date);
+                history.save(&mut pkv);
+                // The seed isn't locked, so that the title screen draws a new one afterwards
+                commands.insert_resource(Seed { value: daily_seed(&date), locked: false });
+                commands.insert_resource(MadnessMode(false));
+                commands.insert_resource(GameMode::daily(date, recorded));
+                state.set(GameState::Cooking).unwrap_or_default();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn cleanup_daily_page(
+    mut commands: Commands,
+    entities: Query<Entity, With<DailyUi>>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
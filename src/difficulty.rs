@@ -0,0 +1,380 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+use crate::ingredients::{Ingredient, Menu};
+use crate::is_headless;
+use crate::restaurant::MENU_SIZE;
+
+pub struct DifficultyPlugin;
+
+/// [`PkvStore`] key of the [`DifficultySettings`]
+pub const DIFFICULTY_SETTINGS: &str = "difficulty";
+/// Shortest menu interval, in seconds
+pub const MIN_MENU_INTERVAL: f64 = 2.;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .init_resource::<DifficultySettings>();
+
+        if is_headless(app) { return; }
+
+        app.add_startup_system(load_difficulty_settings);
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum DifficultyPreset {
+    Easy,
+    Classic,
+    Madness,
+    Custom,
+}
+
+impl DifficultyPreset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DifficultyPreset::Easy => "easy",
+            DifficultyPreset::Classic => "classic",
+            DifficultyPreset::Madness => "madness",
+            DifficultyPreset::Custom => "custom",
+        }
+    }
+
+    /// Next preset of the title screen selector, madness has its own button
+    pub fn next(&self) -> Self {
+        match self {
+            DifficultyPreset::Easy => DifficultyPreset::Classic,
+            DifficultyPreset::Classic => DifficultyPreset::Custom,
+            DifficultyPreset::Madness | DifficultyPreset::Custom => DifficultyPreset::Easy,
+        }
+    }
+}
+
+/// Parameters of the game which make it easier or harder.
+///
+/// The patience of a customer is `extra_time_per_burger + order size * time per ingredient`,
+/// where the time per ingredient is `time_per_ingredient / (1 + orders / ramp_orders)`
+/// and stops decreasing after `max_ramp_orders` orders.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Difficulty {
+    pub preset: DifficultyPreset,
+    /// Patience per ingredient for the first order, in seconds
+    pub time_per_ingredient: f64,
+    /// Patience added to every order, in seconds
+    pub extra_time_per_burger: f64,
+    /// Number of orders after which the patience per ingredient is halved
+    pub ramp_orders: f64,
+    /// Number of orders after which the patience stops decreasing
+    pub max_ramp_orders: u16,
+    /// A burger made faster than `par_extra_time + size * par_time_per_ingredient` earns points
+    pub par_time_per_ingredient: f64,
+    pub par_extra_time: f64,
+    pub lives: i32,
+    /// Seconds between two new ingredients on the menu at the start of the game
+    pub menu_interval: f64,
+    /// Seconds removed from the menu interval for each burger served
+    #[serde(default)]
+    pub menu_acceleration: f64,
+    /// The menu interval doesn't go below this number of seconds (nor below [`MIN_MENU_INTERVAL`])
+    #[serde(default)]
+    pub min_menu_interval: f64,
+    /// Number of items on the menu (bread included), from 5 to `MENU_SIZE + 1`
+    pub menu_size: usize,
+    /// Start with a full menu instead of three ingredients
+    pub full_menu: bool,
+    /// Burgers with at most this many mistakes earn partial credit instead of costing a life, 0 disables partial credit
+    #[serde(default)]
+    pub tolerated_mistakes: usize,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::classic()
+    }
+}
+
+impl Difficulty {
+    pub fn easy() -> Self {
+        Difficulty {
+            preset: DifficultyPreset::Easy,
+            time_per_ingredient: 4.,
+            extra_time_per_burger: 4.,
+            ramp_orders: 20.,
+            max_ramp_orders: 40,
+            lives: 6,
+            menu_interval: 15.,
+            min_menu_interval: 15.,
+            menu_size: 7,
+            tolerated_mistakes: 1,
+            ..Difficulty::classic()
+        }
+    }
+
+    pub fn classic() -> Self {
+        Difficulty {
+            preset: DifficultyPreset::Classic,
+            time_per_ingredient: 3.,
+            extra_time_per_burger: 3.,
+            ramp_orders: 10.,
+            max_ramp_orders: 50,
+            par_time_per_ingredient: 3.,
+            par_extra_time: 3.,
+            lives: 5,
+            menu_interval: 10.,
+            menu_acceleration: 0.,
+            min_menu_interval: 10.,
+            menu_size: MENU_SIZE + 1,
+            full_menu: false,
+            tolerated_mistakes: 0,
+        }
+    }
+
+    pub fn madness() -> Self {
+        Difficulty {
+            preset: DifficultyPreset::Madness,
+            time_per_ingredient: 2.,
+            full_menu: true,
+            ..Difficulty::classic()
+        }
+    }
+
+    pub fn from_preset(preset: DifficultyPreset, custom: &Difficulty) -> Self {
+        match preset {
+            DifficultyPreset::Easy => Difficulty::easy(),
+            DifficultyPreset::Classic => Difficulty::classic(),
+            DifficultyPreset::Madness => Difficulty::madness(),
+            DifficultyPreset::Custom => Difficulty { preset: DifficultyPreset::Custom, ..custom.clone() },
+        }
+    }
+
+    /// Reads custom difficulty parameters from a RON file
+    pub fn from_file(path: &str) -> anyhow::Result<Difficulty> {
+        let difficulty: Difficulty = ron::from_str(&std::fs::read_to_string(path)?)?;
+        difficulty.validate()?;
+        Ok(Difficulty { preset: DifficultyPreset::Custom, ..difficulty })
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(self.time_per_ingredient > 0. && self.extra_time_per_burger >= 0., "patience must be positive");
+        anyhow::ensure!(self.ramp_orders > 0., "ramp_orders must be positive");
+        anyhow::ensure!(self.lives > 0, "lives must be positive");
+        anyhow::ensure!(self.menu_interval >= MIN_MENU_INTERVAL, "menu_interval must be at least {}", MIN_MENU_INTERVAL);
+        anyhow::ensure!(self.menu_acceleration >= 0., "menu_acceleration can't be negative");
+        anyhow::ensure!((5..=MENU_SIZE + 1).contains(&self.menu_size), "menu_size must be between 5 and {}", MENU_SIZE + 1);
+        Ok(())
+    }
+
+    /// Time given to the customer to prepare their order
+    pub fn waiting_time(&self, order_size: usize, order_nb: u16) -> Duration {
+        let ramp = 1. + order_nb.min(self.max_ramp_orders) as f64 / self.ramp_orders;
+        Duration::from_secs_f64(self.extra_time_per_burger + order_size as f64 * self.time_per_ingredient / ramp)
+    }
+
+    /// Time between two new ingredients on the menu after `order_nb` burgers
+    pub fn menu_interval(&self, order_nb: u16) -> Duration {
+        let interval = self.menu_interval - order_nb as f64 * self.menu_acceleration;
+        Duration::from_secs_f64(interval.max(self.min_menu_interval).max(MIN_MENU_INTERVAL))
+    }
+
+    /// Time under which a burger of `size` ingredients earns points
+    pub fn par_time(&self, size: usize) -> f64 {
+        self.par_extra_time + size as f64 * self.par_time_per_ingredient
+    }
+
+    /// Number of ingredients on the menu when the game starts
+    pub fn starting_menu_size(&self) -> usize {
+        if self.full_menu { self.menu_size } else { 1 }
+    }
+}
+
+/// Kind of game being played, with the difficulty it is played at
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GameMode {
+    /// The game ends when the player runs out of lives
+    Classic(Difficulty),
+    /// Practice without lives nor patience
+    Zen(Difficulty),
+    /// Best score in a fixed time, without lives
+    TimeAttack {
+        difficulty: Difficulty,
+        /// Length of the game in seconds
+        time_limit: f64,
+        /// Seconds added to the time limit every [`crate::score::CHAIN_BONUS_STREAK`] burgers of a chain
+        chain_bonus: f64,
+    },
+    /// Classic game with the seed of the day
+    Daily {
+        difficulty: Difficulty,
+        /// UTC date of the challenge when the game was started, `YYYY-MM-DD`
+        date: String,
+        /// Only the first game of the day is recorded in the [`crate::daily::DailyHistory`]
+        recorded: bool,
+    },
+    /// Zen game with the guide of [`crate::tutorial`]
+    Tutorial(Difficulty),
+    /// A day of the campaign, with a goal and some of the ingredients (see [`crate::campaign`])
+    Campaign {
+        difficulty: Difficulty,
+        /// Index of the day in [`crate::campaign::Campaign::days`]
+        day: usize,
+        /// Ingredients the chef can write on the menu
+        ingredients: Vec<Ingredient>,
+    },
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        GameMode::Classic(Difficulty::classic())
+    }
+}
+
+impl GameMode {
+    pub fn zen() -> Self {
+        GameMode::Zen(Difficulty::classic())
+    }
+
+    pub fn time_attack() -> Self {
+        GameMode::TimeAttack {
+            difficulty: Difficulty { menu_interval: 5., min_menu_interval: 5., ..Difficulty::classic() },
+            time_limit: 120.,
+            chain_bonus: 5.,
+        }
+    }
+
+    pub fn daily(date: String, recorded: bool) -> Self {
+        GameMode::Daily { difficulty: Difficulty::classic(), date, recorded }
+    }
+
+    pub fn tutorial() -> Self {
+        GameMode::Tutorial(Difficulty::classic())
+    }
+
+    /// Classic game with only some of the ingredients, the menu is smaller when there are few of them
+    pub fn campaign(day: usize, ingredients: Vec<Ingredient>) -> Self {
+        let difficulty = Difficulty { menu_size: ingredients.len().clamp(5, MENU_SIZE + 1), ..Difficulty::classic() };
+        GameMode::Campaign { difficulty, day, ingredients }
+    }
+
+    pub fn difficulty(&self) -> &Difficulty {
+        match self {
+            GameMode::Classic(difficulty)
+            | GameMode::Zen(difficulty)
+            | GameMode::Tutorial(difficulty)
+            | GameMode::TimeAttack { difficulty, .. }
+            | GameMode::Daily { difficulty, .. }
+            | GameMode::Campaign { difficulty, .. } => difficulty,
+        }
+    }
+
+    /// Name of the mode, the one of the difficulty for classic games
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Classic(difficulty) => difficulty.preset.name(),
+            GameMode::Zen(_) => "zen",
+            GameMode::TimeAttack { .. } => "time attack",
+            GameMode::Daily { .. } => "daily",
+            GameMode::Tutorial(_) => "tutorial",
+            GameMode::Campaign { .. } => "campaign",
+        }
+    }
+
+    /// Customers wait as long as needed and mistakes don't cost lives, the game never ends
+    pub fn is_zen(&self) -> bool {
+        matches!(self, GameMode::Zen(_) | GameMode::Tutorial(_))
+    }
+
+    /// The game ends when the player runs out of lives
+    pub fn has_lives(&self) -> bool {
+        matches!(self, GameMode::Classic(_) | GameMode::Daily { .. } | GameMode::Campaign { .. })
+    }
+
+    /// Length of the game in seconds, if it has a time limit
+    pub fn time_limit(&self) -> Option<f64> {
+        match self {
+            GameMode::TimeAttack { time_limit, .. } => Some(*time_limit),
+            _ => None,
+        }
+    }
+
+    /// Seconds added to the time limit by a chain
+    pub fn chain_bonus(&self) -> f64 {
+        match self {
+            GameMode::TimeAttack { chain_bonus, .. } => *chain_bonus,
+            _ => 0.,
+        }
+    }
+
+    /// Ingredients of `menu` served in this game
+    pub fn menu(&self, menu: &Menu) -> Menu {
+        match self {
+            GameMode::Campaign { ingredients, .. } => menu.restricted(ingredients),
+            _ => menu.clone(),
+        }
+    }
+
+    /// Checks that the game can be played with `menu`
+    pub fn validate(&self, menu: &Menu) -> anyhow::Result<()> {
+        let difficulty = self.difficulty();
+        difficulty.validate()?;
+        let served = self.menu(menu);
+        anyhow::ensure!(!served.meats.is_empty(), "no meat");
+        anyhow::ensure!(!served.starters.is_empty(), "no starter");
+        // The chef always needs a dish which isn't on the menu yet
+        anyhow::ensure!(served.ingredients.len() >= difficulty.menu_size, "less than {} ingredients", difficulty.menu_size);
+        Ok(())
+    }
+}
+
+/// Difficulty chosen on the title screen for classic games
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DifficultySettings {
+    pub preset: DifficultyPreset,
+    /// Parameters of the Custom preset
+    pub custom: Difficulty,
+}
+
+impl Default for DifficultySettings {
+    fn default() -> Self {
+        DifficultySettings {
+            preset: DifficultyPreset::Classic,
+            custom: Difficulty { preset: DifficultyPreset::Custom, ..Difficulty::classic() },
+        }
+    }
+}
+
+impl DifficultySettings {
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::from_preset(self.preset, &self.custom)
+    }
+}
+
+fn load_difficulty_settings(mut settings: ResMut<DifficultySettings>, pkv: Res<PkvStore>) {
+    if let Ok(saved) = pkv.get::<DifficultySettings>(DIFFICULTY_SETTINGS) {
+        *settings = saved;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn menu_interval_accelerates_down_to_its_minimum() {
+        let custom = Difficulty {
+            preset: DifficultyPreset::Custom,
+            menu_acceleration: 0.1,
+            min_menu_interval: 5.,
+            ..Difficulty::classic()
+        };
+        assert_eq!(custom.menu_interval(0), Duration::from_secs(10));
+        assert!(custom.menu_interval(20) < custom.menu_interval(10));
+        assert_eq!(custom.menu_interval(1000), Duration::from_secs(5));
+        // The presets keep the same interval for the whole game
+        assert_eq!(Difficulty::madness().menu_interval(1000), Duration::from_secs(10));
+        assert_eq!(Difficulty::classic().menu_interval(1000), Duration::from_secs(10));
+    }
+}
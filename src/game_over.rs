@@ -1,35 +1,80 @@
 use bevy::prelude::*;
 use bevy_pkv::PkvStore;
 
-use crate::{GameState, spawn_sprite};
+use crate::{GameState, spawn_sprite, spawn_text};
 use crate::audio::{BGM, PlayBgmEvent};
 use crate::button::spawn_button;
-use crate::input::{KeyboardReleaseEvent, process_input};
+use crate::difficulty::GameMode;
+use crate::input::{Actions, KeyboardReleaseEvent};
+use crate::leaderboard::{INITIALS_LENGTH, Leaderboard, LEADERBOARD_SIZE, LeaderboardEntry, today};
 use crate::loading::{FontAssets, TextureAssets};
+use crate::replay::ReplayRecorder;
 use crate::score::Score;
+use crate::stats::RunStats;
 
 pub struct GameOverPlugin;
 
+/// Height of the first line of the receipt printed on the bill
+const RECEIPT_TOP: f32 = 93.;
+
 #[derive(Component)]
 struct GameOverUi;
 
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(init_game_over))
+        app.add_system_set(
+                SystemSet::on_enter(GameState::GameOver)
+                    .with_system(spawn_game_over_scene)
+                    .with_system(init_game_over)
+                    .with_system(ignore_held_keys),
+            )
             .add_system_set(
                 SystemSet::on_update(GameState::GameOver)
                     .with_system(update_game_over)
-                    .with_system(process_input),
+                    .with_system(update_leaderboard.after(update_game_over)),
             )
             .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(clean_game_over));
     }
 }
 
-fn init_game_over(
-    score: Res<Score>,
+/// Leaderboard of the mode which was played
+struct GameOverLeaderboard {
+    /// Name of the mode
+    mode: &'static str,
+    leaderboard: Leaderboard,
+    /// Entry of the last game while the player types their initials
+    pending: Option<LeaderboardEntry>,
+    /// Rank of the last game in the leaderboard
+    rank: Option<usize>,
+}
+
+impl GameOverLeaderboard {
+    /// The leaderboard, with the pending entry at its rank
+    fn preview(&self) -> Vec<LeaderboardEntry> {
+        let mut leaderboard = self.leaderboard.clone();
+        if let Some(entry) = &self.pending {
+            let mut initials = entry.initials.clone();
+            while initials.len() < INITIALS_LENGTH { initials.push('_'); }
+            leaderboard.insert(LeaderboardEntry { initials, ..entry.clone() });
+        }
+        leaderboard.entries
+    }
+}
+
+#[derive(Component)]
+struct NameText;
+
+/// Instructions shown while the player types their initials
+#[derive(Component)]
+struct NamePrompt;
+
+#[derive(Component)]
+struct LeaderboardRow;
+
+/// Background, music and buttons of the game over screen
+fn spawn_game_over_scene(
     mut commands: Commands,
     mut bgm: EventWriter<PlayBgmEvent>,
-    pkv: Res<PkvStore>,
     textures: Res<TextureAssets>,
     fonts: Res<FontAssets>,
 ) {
@@ -40,37 +85,6 @@ fn init_game_over(
     spawn_sprite(&mut commands, textures.game_over.clone(), Vec3::new(160. - 136., 136. - 23., 1.)).insert(GameOverUi);
     spawn_sprite(&mut commands, textures.end_bill.clone(), Vec3::new(160. - 64., -24., 2.)).insert(GameOverUi);
 
-    let texts = [
-        (format!("YOUR SCORE:"), Vec2::new(108.0, 77.0)),
-        (score.score.to_string(), Vec2::new(108.0, 77.0 - 8. * 1.)),
-        (format!("ALL-TIME BEST: (CLASSIC)"), Vec2::new(108.0, 77.0 - 8. * 3.)),
-        (pkv.get::<String>("classic").unwrap_or("0".to_string()), Vec2::new(108.0, 77.0 - 8. * 4.)),
-        (format!("ALL-TIME BEST: (MADNESS)"), Vec2::new(108.0, 77.0 - 8. * 6.)),
-        (pkv.get::<String>("madness").unwrap_or("0".to_string()), Vec2::new(108.0, 77.0 - 8. * 7.)),
-    ];
-
-    for (text, pos) in texts {
-        commands
-            .spawn_bundle(Text2dBundle {
-                text: Text {
-                    sections: vec![
-                        TextSection {
-                            value: text,
-                            style: TextStyle {
-                                font: fonts.axg.clone(),
-                                font_size: 16.0,
-                                color: Color::BLACK,
-                            },
-                        },
-                    ],
-                    alignment: TextAlignment::BOTTOM_LEFT,
-                },
-                transform: Transform::from_xyz(pos.x, pos.y, 3.),
-                ..Default::default()
-            })
-            .insert(GameOverUi);
-    }
-
     let buttons = [
         ('m', Vec2::new(124., 8.)),
         ('i', Vec2::new(124. + 20., 8.)),
@@ -84,17 +98,174 @@ fn init_game_over(
     }
 }
 
+/// Receipt of the game and leaderboard of its mode
+fn init_game_over(
+    score: Res<Score>,
+    stats: Res<RunStats>,
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    recorder: Res<ReplayRecorder>,
+    pkv: Res<PkvStore>,
+    fonts: Res<FontAssets>,
+) {
+    // Games played by the bot or watched from a replay don't enter the leaderboard,
+    // nor the days of the campaign which have their own goals
+    let leaderboard = Leaderboard::load(&pkv, mode.name());
+    let ranked = recorder.is_player_game() && !matches!(*mode, GameMode::Campaign { .. });
+    let rank = if ranked { leaderboard.rank(score.score) } else { None };
+    let pending = rank.map(|_| LeaderboardEntry {
+        initials: String::new(),
+        score: score.score,
+        burgers: stats.burgers,
+        longest_chain: stats.longest_chain,
+        date: today(),
+    });
+
+    // Itemized receipt of the game
+    let stats_lines = [
+        ("BURGERS", stats.burgers.to_string()),
+        ("MISSED", stats.misses.to_string()),
+        ("TOO SLOW", stats.out_of_time.to_string()),
+        ("LONGEST CHAIN", stats.longest_chain.to_string()),
+        ("SEC/INGREDIENT", stats.time_per_ingredient().map_or("-".to_string(), |t| format!("{:.1}", t))),
+        ("KEY ACCURACY", stats.keystroke_accuracy().map_or("-".to_string(), |a| format!("{:.0}%", 100. * a))),
+        ("MOST MISSED", stats.most_missed_ingredient().map_or("-".to_string(), |i| i.name().to_uppercase())),
+        ("TOTAL", score.score.to_string()),
+    ];
+    for (i, (label, value)) in stats_lines.into_iter().enumerate() {
+        // The total is separated from the items
+        let y = RECEIPT_TOP - 8. * i as f32 - if label == "TOTAL" { 4. } else { 0. };
+        spawn_text(&mut commands, &fonts, receipt_line(label, &value), Vec2::new(108.0, y), Color::BLACK).insert(GameOverUi);
+    }
+
+    let last_line = Vec2::new(108.0, RECEIPT_TOP - 8. * 8. - 4.);
+    match rank {
+        Some(_) => {
+            spawn_text(&mut commands, &fonts, name_text(rank, &pending), last_line, Color::BLACK)
+                .insert(NameText)
+                .insert(GameOverUi);
+            spawn_text(
+                &mut commands, &fonts,
+                "NEW HIGH SCORE! TYPE YOUR INITIALS AND PRESS SPACE".to_string(),
+                Vec2::new(4., 166.), Color::WHITE,
+            ).insert(NamePrompt).insert(GameOverUi);
+        }
+        None => {
+            let best = receipt_line(
+                &format!("BEST {}", mode.name().to_uppercase()),
+                &leaderboard.best_score().max(score.score).to_string(),
+            );
+            spawn_text(&mut commands, &fonts, best, last_line, Color::BLACK).insert(GameOverUi);
+        }
+    }
+
+    spawn_text(
+        &mut commands, &fonts,
+        format!("{} TOP {}", mode.name().to_uppercase(), LEADERBOARD_SIZE),
+        Vec2::new(4., 100.), Color::WHITE,
+    ).insert(GameOverUi);
+    spawn_text(&mut commands, &fonts, "BURGERS CHAIN".to_string(), Vec2::new(228., 100.), Color::WHITE).insert(GameOverUi);
+
+    commands.insert_resource(GameOverLeaderboard { mode: mode.name(), leaderboard, pending, rank });
+}
+
+/// Keys held when the game ended, their release isn't typed as an initial
+#[derive(Default)]
+struct HeldKeys(Vec<char>);
+
+fn ignore_held_keys(
+    mut commands: Commands,
+    input: Res<Actions>,
+    mut release_events: ResMut<Events<KeyboardReleaseEvent>>,
+) {
+    release_events.clear();
+    commands.insert_resource(HeldKeys(input.pressed.clone()));
+}
+
 fn update_game_over(
     mut events: EventReader<KeyboardReleaseEvent>,
     mut state: ResMut<State<GameState>>,
+    mut board: ResMut<GameOverLeaderboard>,
+    mut held: ResMut<HeldKeys>,
+    mut pkv: ResMut<PkvStore>,
 ) {
     for KeyboardReleaseEvent(l) in events.iter() {
-        if *l == 'm' || *l == 'i' || *l == 'a' {
-            state.set(GameState::TitleScreen).unwrap_or_default();
+        if let Some(i) = held.0.iter().position(|key| key == l) {
+            held.0.remove(i);
+            continue;
+        }
+        let board = &mut *board;
+        match &mut board.pending {
+            // Type initials
+            Some(entry) => match *l {
+                'a'..='z' if entry.initials.len() < INITIALS_LENGTH => entry.initials.push(l.to_ascii_uppercase()),
+                '<' => { entry.initials.pop(); }
+                ' ' if entry.initials.len() == INITIALS_LENGTH => {
+                    board.leaderboard.insert(entry.clone());
+                    board.leaderboard.save(&mut pkv, board.mode);
+                    board.pending = None;
+                }
+                _ => {}
+            },
+            None => {
+                if *l == 'm' || *l == 'i' || *l == 'a' {
+                    state.set(GameState::TitleScreen).unwrap_or_default();
+                }
+            }
         }
     }
 }
 
+fn update_leaderboard(
+    mut commands: Commands,
+    board: Res<GameOverLeaderboard>,
+    fonts: Res<FontAssets>,
+    rows: Query<Entity, With<LeaderboardRow>>,
+    prompts: Query<Entity, With<NamePrompt>>,
+    mut name_texts: Query<&mut Text, With<NameText>>,
+) {
+    if !board.is_changed() { return; }
+
+    match &board.pending {
+        Some(_) => {
+            for mut text in name_texts.iter_mut() {
+                text.sections[0].value = name_text(board.rank, &board.pending);
+            }
+        }
+        None => {
+            for entity in prompts.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+
+    for entity in rows.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for (i, entry) in board.preview().iter().enumerate() {
+        let color = if board.rank == Some(i) { Color::rgb(1., 0.85, 0.3) } else { Color::WHITE };
+        let y = 92. - 8. * i as f32;
+        let date = entry.date.get(5..).unwrap_or("");
+        spawn_text(&mut commands, &fonts, format!("{:>2} {} {}", i + 1, entry.initials, entry.score), Vec2::new(4., y), color)
+            .insert(LeaderboardRow)
+            .insert(GameOverUi);
+        spawn_text(&mut commands, &fonts, format!("{:>3} {:>3} {}", entry.burgers, entry.longest_chain, date), Vec2::new(228., y), color)
+            .insert(LeaderboardRow)
+            .insert(GameOverUi);
+    }
+}
+
+fn name_text(rank: Option<usize>, entry: &Option<LeaderboardEntry>) -> String {
+    let mut initials = entry.as_ref().map(|e| e.initials.clone()).unwrap_or_default();
+    while initials.len() < INITIALS_LENGTH { initials.push('_'); }
+    format!("HIGH SCORE #{}: {}", rank.map_or(0, |r| r + 1), initials)
+}
+
+/// `label` and `value` separated by dots, like on a receipt
+fn receipt_line(label: &str, value: &str) -> String {
+    format!("{:.<15}{:.>8}", label, value)
+}
+
 fn clean_game_over(
     mut commands: Commands,
     spawned_ui_elements: Query<Entity, With<GameOverUi>>,
@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use bevy::core::CorePlugin;
+use bevy::ecs::system::Resource;
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use crate::{GamePlugin, GameState, Headless};
+use crate::campaign::Campaign;
+use crate::cooking::MadnessMode;
+use crate::difficulty::{Difficulty, DifficultyPreset, GameMode};
+use crate::ingredients::{Menu, MenuBook};
+use crate::input::KeyboardEvent;
+use crate::order::Order;
+use crate::replay::{Playback, Replay};
+use crate::rng::Seed;
+
+/// Duration of a frame in a headless game
+pub const FRAME: Duration = Duration::from_millis(16);
+
+/// The gameplay logic of the restaurant, without window, renderer, audio, sprites or tweens.
+///
+/// Like `MinimalPlugins`, except that time only advances when the app is updated through this struct,
+/// so games are reproducible.
+pub struct HeadlessApp {
+    pub app: App,
+    now: Instant,
+}
+
+impl HeadlessApp {
+    pub fn new(menu: Menu) -> Self {
+        let now = Instant::now();
+        let mut time = Time::default();
+        time.update_with_instant(now);
+
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .insert_resource(time)
+            .insert_resource(Headless)
+            .insert_resource(menu)
+            .insert_resource(default_campaign())
+            .add_plugin(GamePlugin);
+
+        // The first frame enters the initial state, a new one can only be set after it
+        let mut headless = HeadlessApp { app, now };
+        headless.update();
+        headless
+    }
+
+    /// Headless app serving the first menu of `assets/menus/default.menus.ron`
+    pub fn with_default_menu() -> Self {
+        HeadlessApp::new(default_menu())
+    }
+
+    /// Starts cooking in classic or madness mode
+    pub fn start(&mut self, seed: u64, madness: bool) {
+        let difficulty = if madness { Difficulty::madness() } else { Difficulty::classic() };
+        self.start_with_difficulty(seed, difficulty);
+    }
+
+    /// Starts cooking in classic mode at `difficulty`
+    pub fn start_with_difficulty(&mut self, seed: u64, difficulty: Difficulty) {
+        self.start_with_mode(seed, GameMode::Classic(difficulty));
+    }
+
+    pub fn start_with_mode(&mut self, seed: u64, mode: GameMode) {
+        self.app.insert_resource(Seed { value: seed, locked: true });
+        self.app.insert_resource(MadnessMode(mode.difficulty().preset == DifficultyPreset::Madness));
+        self.app.insert_resource(mode);
+        self.app.world.resource_mut::<State<GameState>>().set(GameState::Cooking).unwrap();
+        self.update();
+    }
+
+    /// Runs one frame
+    pub fn update(&mut self) {
+        self.now += FRAME;
+        self.app.world.resource_mut::<Time>().update_with_instant(self.now);
+        self.app.update();
+    }
+
+    /// Runs frames until `duration` has elapsed
+    pub fn advance(&mut self, duration: Duration) {
+        let frames = (duration.as_secs_f64() / FRAME.as_secs_f64()).ceil() as u32;
+        for _ in 0..frames {
+            self.update();
+        }
+    }
+
+    /// Presses a key and runs one frame
+    pub fn press(&mut self, key: char) {
+        self.app.world.resource_mut::<Events<KeyboardEvent>>().send(KeyboardEvent(key));
+        self.update();
+    }
+
+    /// Presses all the keys of `keys` in the same frame
+    pub fn press_together(&mut self, keys: &str) {
+        let mut events = self.app.world.resource_mut::<Events<KeyboardEvent>>();
+        for key in keys.chars() {
+            events.send(KeyboardEvent(key));
+        }
+        self.update();
+    }
+
+    /// Presses each key of `keys`, one per frame
+    pub fn type_keys(&mut self, keys: &str) {
+        for key in keys.chars() {
+            self.press(key);
+        }
+    }
+
+    /// Goes back to the title screen and plays `replay`
+    pub fn play(&mut self, replay: Replay) {
+        if self.state() != GameState::TitleScreen {
+            self.app.world.resource_mut::<State<GameState>>().set(GameState::TitleScreen).unwrap();
+            self.update();
+        }
+        self.app.insert_resource(Playback::new(replay));
+        self.update();
+    }
+
+    /// Keys to type to make the current order
+    pub fn order_keys(&self) -> String {
+        self.resource::<Order>().ingredients.iter().map(|i| i.key()).collect()
+    }
+
+    pub fn resource<R: Resource>(&self) -> &R {
+        self.app.world.resource::<R>()
+    }
+
+    pub fn state(&self) -> GameState {
+        self.resource::<State<GameState>>().current().clone()
+    }
+}
+
+/// The campaign of `assets/menus/default.campaign.ron`
+pub fn default_campaign() -> Campaign {
+    Campaign::from_bytes(include_bytes!("../assets/menus/default.campaign.ron")).unwrap()
+}
+
+pub fn default_menu() -> Menu {
+    MenuBook::from_bytes(include_bytes!("../assets/menus/default.menus.ron"))
+        .unwrap()
+        .menus
+        .remove(0)
+}
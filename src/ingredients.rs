@@ -1,11 +1,14 @@
 use std::cmp::min;
+use std::fmt;
 
+use bevy::reflect::TypeUuid;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::audio::SFX;
 use crate::restaurant::MENU_SIZE;
 
-#[derive(Clone, Eq, PartialEq, Copy, Hash)]
+#[derive(Clone, Eq, PartialEq, Copy, Hash, Debug, Serialize, Deserialize)]
 pub enum Ingredient {
     Bread,
     Steak,
@@ -25,13 +28,25 @@ pub enum Ingredient {
 }
 
 impl Ingredient {
-    pub fn is_meat(&self) -> bool {
-        match self {
-            Ingredient::Steak | Ingredient::Chicken => true,
-            _ => false
-        }
-    }
+    pub const ALL: [Ingredient; 15] = [
+        Ingredient::Bread,
+        Ingredient::Steak,
+        Ingredient::Tomato,
+        Ingredient::Salad,
+        Ingredient::Egg,
+        Ingredient::Pickles,
+        Ingredient::Avocado,
+        Ingredient::Cheese,
+        Ingredient::Bacon,
+        Ingredient::Jalapeno,
+        Ingredient::Chicken,
+        Ingredient::Mushrooms,
+        Ingredient::Onions,
+        Ingredient::Mayo,
+        Ingredient::Ketchup,
+    ];
 
+    /// Ingredient of a key of the game, see [`Ingredient::key`]
     pub fn from_key(key: &char) -> Option<Self> {
         match key {
             'b' => Some(Self::Bread),
@@ -74,6 +89,10 @@ impl Ingredient {
         }
     }
 
+    /// Key of the ingredient in the game, which is also its key with the default QWERTY bindings.
+    ///
+    /// [`crate::input::KeyboardEvent`]s, replays and the bot use these keys:
+    /// the [`crate::bindings::KeyBindings`] translate the keys of the player into them.
     pub fn key(&self) -> char {
         match self {
             Ingredient::Bread => 'b',
@@ -136,159 +155,308 @@ impl Ingredient {
     }
 }
 
-#[derive(Clone, Copy)]
-pub enum Menu {
-    Uno,
+/// A menu served in the restaurant, loaded from a `.menus.ron` file (see [`MenuBook`]).
+///
+/// Bread is implicit: it is always on the menu, at the top and at the bottom of every burger.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Menu {
+    pub name: String,
+    /// Ingredients the chef can write on the menu
+    pub ingredients: Vec<Ingredient>,
+    /// Every burger contains one of these (one of them is always on the menu)
+    pub meats: Vec<Ingredient>,
+    /// At most one sauce is put on top of a burger
+    pub sauces: Vec<Ingredient>,
+    /// One of these is on the menu at the start of a classic game
+    pub starters: Vec<Ingredient>,
+    /// Number of times each ingredient can appear in a burger
+    pub copies: usize,
+    /// Bounds on the number of layers of a burger, breads included
+    pub min_size: usize,
+    pub max_size: usize,
+    /// Weights of the number of random ingredients put in a burger
+    pub size_weights: SizeWeights,
+    pub sauce_chance: f64,
+    pub triple_meat_chance: f64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub enum SizeWeights {
+    /// P(x) = (x + 1) / k, bigger burgers are more likely
+    Linear,
+    Uniform,
+    /// Weight of 0, 1, 2... random ingredients, missing values weigh 0
+    Custom(Vec<u32>),
 }
 
-const MAX_SIZE_OF_BURGER: usize = 10;
+/// A list of menus, as found in a `.menus.ron` asset.
+///
+/// The first menu of the list is served in the restaurant, unless the player chooses another one
+/// (see [`crate::loading::MENU_CHOICE`]).
+#[derive(Clone, Debug, Deserialize, TypeUuid)]
+#[uuid = "4c1fb1a6-4d64-4a8e-9a57-0d5e8d2b7c31"]
+pub struct MenuBook {
+    pub menus: Vec<Menu>,
+}
 
-impl Menu {
-    // pub fn name(&self) -> &'static str {
-    //     match self {
-    //         Menu::Uno => "Menu Uno",
-    //     }
-    // }
+#[derive(Debug)]
+pub enum MenuError {
+    NoMenu,
+    DuplicateName(String),
+    Empty(String),
+    Bread(String),
+    DuplicateIngredient(String, Ingredient),
+    NotInMenu(String, Ingredient),
+    NoMeat(String),
+    NoStarter(String),
+    TooFewIngredients(String),
+    Size(String),
+    Weights(String),
+    Chance(String),
+}
 
-    pub fn ingredients(&self) -> Vec<Ingredient> {
+impl fmt::Display for MenuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Menu::Uno => vec![
-                Ingredient::Steak,
-                Ingredient::Salad,
-                Ingredient::Tomato,
-                Ingredient::Egg,
-                Ingredient::Pickles,
-                Ingredient::Avocado,
-                Ingredient::Cheese,
-                Ingredient::Bacon,
-                Ingredient::Jalapeno,
-                Ingredient::Chicken,
-                Ingredient::Mushrooms,
-                Ingredient::Onions,
-                Ingredient::Mayo,
-                Ingredient::Ketchup,
-            ],
+            MenuError::NoMenu => write!(f, "the menu book doesn't contain any menu"),
+            MenuError::DuplicateName(name) => write!(f, "several menus are called \"{}\"", name),
+            MenuError::Empty(name) => write!(f, "\"{}\" doesn't have any ingredient", name),
+            MenuError::Bread(name) => write!(f, "\"{}\": bread is implicit and can't be listed", name),
+            MenuError::DuplicateIngredient(name, i) => write!(f, "\"{}\": {:?} is listed twice", name, i),
+            MenuError::NotInMenu(name, i) => write!(f, "\"{}\": {:?} isn't in the ingredients list", name, i),
+            MenuError::NoMeat(name) => write!(f, "\"{}\" doesn't have any meat", name),
+            MenuError::NoStarter(name) => write!(f, "\"{}\" doesn't have any starter", name),
+            MenuError::TooFewIngredients(name) => write!(f, "\"{}\" needs more than {} ingredients", name, MENU_SIZE),
+            MenuError::Size(name) => write!(f, "\"{}\": sizes must satisfy 3 <= min_size <= max_size and max_size >= 4", name),
+            MenuError::Weights(name) => write!(f, "\"{}\": custom size weights must not all be 0", name),
+            MenuError::Chance(name) => write!(f, "\"{}\": chances must be between 0 and 1", name),
+        }
+    }
+}
+
+impl std::error::Error for MenuError {}
+
+impl MenuBook {
+    /// Reads and validates a `.menus.ron` file
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<MenuBook> {
+        let book = ron::de::from_bytes::<MenuBook>(bytes)?;
+        book.validate()?;
+        Ok(book)
+    }
+
+    /// The menu called `name`
+    pub fn get(&self, name: &str) -> Option<&Menu> {
+        self.menus.iter().find(|menu| menu.name == name)
+    }
+
+    /// The menu following the one called `name`, the first one after the last one
+    pub fn next(&self, name: &str) -> &Menu {
+        let i = self.menus.iter().position(|menu| menu.name == name).map_or(0, |i| i + 1);
+        &self.menus[i % self.menus.len()]
+    }
+
+    pub fn validate(&self) -> Result<(), MenuError> {
+        if self.menus.is_empty() { return Err(MenuError::NoMenu); }
+        for (i, menu) in self.menus.iter().enumerate() {
+            if self.menus[..i].iter().any(|m| m.name == menu.name) {
+                return Err(MenuError::DuplicateName(menu.name.clone()));
+            }
+            menu.validate()?;
         }
+        Ok(())
     }
+}
+
+impl Menu {
+    pub fn validate(&self) -> Result<(), MenuError> {
+        let name = || self.name.clone();
 
-    pub fn generate_order(&self, ingredients: &Vec<Ingredient>) -> Vec<Ingredient> {
-        return match self {
-            Menu::Uno => {
-                let mut rng = thread_rng();
-
-                // Choose a meat for the burger
-                let meat = if ingredients.contains(&Ingredient::Chicken) && ingredients.contains(&Ingredient::Steak) {
-                    if random() { Ingredient::Chicken } else { Ingredient::Steak }
-                } else {
-                    *ingredients.iter().find(|i| i.is_meat()).unwrap()
-                };
-
-                // random_ingredients are the ingredients that will be chosen at random
-                let mut random_ingredients: Vec<Ingredient> = ingredients
-                    .into_iter()
-                    // Remove the special ingredients that we don't want interfering with our stuff
-                    .filter(|i| match i {
-                        Ingredient::Bread => false,
-                        Ingredient::Steak => false,
-                        Ingredient::Chicken => false,
-                        Ingredient::Mayo => false,
-                        Ingredient::Ketchup => false,
-                        _ => true,
-                    })
-                    .copied()
-                    .collect();
-
-                // A bit of sauce intelligence to determine how much sauce we are going to put
-                let possible_ketchup = ingredients.contains(&Ingredient::Ketchup);
-                let possible_mayo = ingredients.contains(&Ingredient::Mayo);
-                let is_there_sauce = random() && (possible_ketchup || possible_mayo);
-                let nb_sauces = if is_there_sauce { 1 } else { 0 };
-
-                // Double every ingredient
-                let ri: Vec<Ingredient> = random_ingredients.iter().copied().collect();
-                random_ingredients.extend(ri.into_iter());
-
-                // Possible double meat
-                random_ingredients.push(meat);
-
-                // Choose a number of ingredients
-                // We guard this otherwise rand fires a runtime error
-                let nb = if random_ingredients.len() <= 0 {
-                    0
-                } else {
-                    // The maximum number of ingredients that is possible to generate in this configuration
-                    // MAX_SIZE_OF_BURGER - nb_bread - nb_meat_inserted_at_the_end - nb_sauces
-                    let max_nb_ingredients = MAX_SIZE_OF_BURGER - 2 - 1 - nb_sauces;
-                    // We use a linear distribution, ie
-                    //      P(x) = (x + 1) / k
-                    // where k is a normalisation constant
-                    //       1 avoids the weight 0 for 0.
-                    // And we go up further one, because it is removed from the range (thus '+ 2')
-                    let weights = 1..min(random_ingredients.len(), max_nb_ingredients) + 2;
-                    let nb_dist = rand::distributions::WeightedIndex::new(weights).unwrap();
-                    // We sample in that dist
-                    rng.sample(nb_dist)
-                };
-
-                // We chose nb ingredients from the possible ingredients
-                let mut recipe: Vec<Ingredient> = random_ingredients
-                    .choose_multiple(&mut rng, nb)
-                    .into_iter()
-                    .copied()
-                    .collect();
-
-                // Push the necessary meat at a random index
-                recipe.push(meat);
-                recipe.shuffle(&mut rng);
-
-                // Triple meat possibility
-                if random::<f32>() < 0.1 && recipe.len() < MAX_SIZE_OF_BURGER - 2 {
-                    recipe.push(meat);
-                    recipe.shuffle(&mut rng);
-                }
-
-                // Add maybe some sauces on top of it
-                if is_there_sauce {
-                    if !possible_mayo || (possible_ketchup && possible_mayo && random()) {
-                        recipe.push(Ingredient::Ketchup)
-                    } else {
-                        recipe.push(Ingredient::Mayo)
-                    }
-                }
-
-                // Add the bread on top and at bottom
-                recipe.insert(0, Ingredient::Bread);
-                recipe.push(Ingredient::Bread);
-
-                recipe
+        if self.ingredients.is_empty() { return Err(MenuError::Empty(name())); }
+        if self.ingredients.len() <= MENU_SIZE { return Err(MenuError::TooFewIngredients(name())); }
+        for (i, ingredient) in self.ingredients.iter().enumerate() {
+            if *ingredient == Ingredient::Bread { return Err(MenuError::Bread(name())); }
+            if self.ingredients[..i].contains(ingredient) {
+                return Err(MenuError::DuplicateIngredient(name(), *ingredient));
+            }
+        }
+        for ingredient in self.meats.iter().chain(self.sauces.iter()).chain(self.starters.iter()) {
+            if !self.ingredients.contains(ingredient) {
+                return Err(MenuError::NotInMenu(name(), *ingredient));
             }
+        }
+        if self.meats.is_empty() { return Err(MenuError::NoMeat(name())); }
+        if self.starters.is_empty() { return Err(MenuError::NoStarter(name())); }
+
+        if self.min_size < 3 || self.max_size < 4 || self.min_size > self.max_size || self.copies == 0 {
+            return Err(MenuError::Size(name()));
+        }
+        if let SizeWeights::Custom(weights) = &self.size_weights {
+            if weights.iter().all(|&w| w == 0) { return Err(MenuError::Weights(name())); }
+        }
+        let chance_ok = |c: f64| (0. ..=1.).contains(&c);
+        if !chance_ok(self.sauce_chance) || !chance_ok(self.triple_meat_chance) {
+            return Err(MenuError::Chance(name()));
+        }
+        Ok(())
+    }
+
+    /// The same menu, without the ingredients which aren't in `pool`
+    pub fn restricted(&self, pool: &[Ingredient]) -> Menu {
+        let keep = |ingredients: &Vec<Ingredient>| -> Vec<Ingredient> {
+            ingredients.iter().filter(|i| pool.contains(*i)).copied().collect()
         };
+        Menu {
+            ingredients: keep(&self.ingredients),
+            meats: keep(&self.meats),
+            sauces: keep(&self.sauces),
+            starters: keep(&self.starters),
+            ..self.clone()
+        }
     }
 
-    pub fn basic_ingredients(&self, madness_mode: bool) -> Vec<Ingredient> {
-        let ingredients_nb = if madness_mode { MENU_SIZE } else { 1 };
-        match self {
-            Menu::Uno => {
-                let mut ingredients = vec![Ingredient::Bread];
-                ingredients.push(if random() { Ingredient::Steak } else { Ingredient::Chicken });
-                let additional_ingredient =
-                    vec![Ingredient::Salad, Ingredient::Ketchup, Ingredient::Cheese]
-                        .iter()
-                        .choose(&mut thread_rng())
-                        .copied()
-                        .unwrap();
-                ingredients.push(additional_ingredient);
-                while ingredients.len() <= ingredients_nb {
-                    let ingredient = Menu::Uno.ingredients()
-                        .iter()
-                        .filter(|i| !ingredients.contains(*i))
-                        .choose(&mut thread_rng())
-                        .unwrap()
-                        .clone();
-                    ingredients.push(ingredient);
-                }
-                ingredients
+    pub fn is_meat(&self, ingredient: &Ingredient) -> bool {
+        self.meats.contains(ingredient)
+    }
+
+    pub fn is_sauce(&self, ingredient: &Ingredient) -> bool {
+        self.sauces.contains(ingredient)
+    }
+
+    pub fn generate_order<R: Rng>(&self, ingredients: &Vec<Ingredient>, rng: &mut R) -> Vec<Ingredient> {
+        // Choose a meat for the burger
+        let meat = *ingredients
+            .iter()
+            .filter(|i| self.is_meat(i))
+            .choose(rng)
+            .unwrap();
+
+        // random_ingredients are the ingredients that will be chosen at random
+        let mut random_ingredients: Vec<Ingredient> = ingredients
+            .into_iter()
+            // Remove the special ingredients that we don't want interfering with our stuff
+            .filter(|&i| *i != Ingredient::Bread && !self.is_meat(i) && !self.is_sauce(i))
+            .copied()
+            .collect();
+
+        // A bit of sauce intelligence to determine how much sauce we are going to put
+        let possible_sauces: Vec<Ingredient> = ingredients
+            .iter()
+            .filter(|i| self.is_sauce(i))
+            .copied()
+            .collect();
+        let is_there_sauce = rng.gen_bool(self.sauce_chance) && !possible_sauces.is_empty();
+        let nb_sauces = if is_there_sauce { 1 } else { 0 };
+
+        // Allow every ingredient several times
+        let ri: Vec<Ingredient> = random_ingredients.iter().copied().collect();
+        for _ in 1..self.copies {
+            random_ingredients.extend(ri.iter());
+        }
+
+        // Possible double meat
+        random_ingredients.push(meat);
+
+        // Choose a number of ingredients
+        // The maximum number of ingredients that is possible to generate in this configuration
+        // max_size - nb_bread - nb_meat_inserted_at_the_end - nb_sauces
+        let max_nb_ingredients = min(random_ingredients.len(), self.max_size - 2 - 1 - nb_sauces);
+        let min_nb_ingredients = min(self.min_size.saturating_sub(2 + 1 + nb_sauces), max_nb_ingredients);
+        let weights: Vec<u32> = (min_nb_ingredients..=max_nb_ingredients)
+            .map(|nb| match &self.size_weights {
+                // 1 avoids the weight 0 for 0.
+                SizeWeights::Linear => nb as u32 + 1,
+                SizeWeights::Uniform => 1,
+                SizeWeights::Custom(w) => w.get(nb).copied().unwrap_or(0),
+            })
+            .collect();
+        // We guard this otherwise rand fires a runtime error
+        let nb = match rand::distributions::WeightedIndex::new(&weights) {
+            Ok(nb_dist) => min_nb_ingredients + rng.sample(nb_dist),
+            Err(_) => min_nb_ingredients,
+        };
+
+        // We chose nb ingredients from the possible ingredients
+        let mut recipe: Vec<Ingredient> = random_ingredients
+            .choose_multiple(rng, nb)
+            .into_iter()
+            .copied()
+            .collect();
+
+        // Push the necessary meat at a random index
+        recipe.push(meat);
+        recipe.shuffle(rng);
+
+        // Triple meat possibility
+        if rng.gen_bool(self.triple_meat_chance) && recipe.len() < self.max_size - 2 - nb_sauces {
+            recipe.push(meat);
+            recipe.shuffle(rng);
+        }
+
+        // Add maybe some sauces on top of it
+        if is_there_sauce {
+            recipe.push(*possible_sauces.choose(rng).unwrap());
+        }
+
+        // Add the bread on top and at bottom
+        recipe.insert(0, Ingredient::Bread);
+        recipe.push(Ingredient::Bread);
+
+        recipe
+    }
+
+    /// Ingredients on the menu when the game starts: bread, a meat, a starter,
+    /// and other ingredients until there are `size` items
+    pub fn basic_ingredients<R: Rng>(&self, size: usize, rng: &mut R) -> Vec<Ingredient> {
+        let mut ingredients = vec![Ingredient::Bread];
+        ingredients.push(*self.meats.choose(rng).unwrap());
+        let additional_ingredient = self.starters
+            .iter()
+            .filter(|i| !ingredients.contains(*i))
+            .choose(rng)
+            .copied();
+        if let Some(ingredient) = additional_ingredient {
+            ingredients.push(ingredient);
+        }
+        while ingredients.len() < size {
+            let ingredient = self.ingredients
+                .iter()
+                .filter(|i| !ingredients.contains(*i))
+                .choose(rng)
+                .unwrap()
+                .clone();
+            ingredients.push(ingredient);
+        }
+        ingredients
+    }
+}
+
+/// Number of ingredients to add, remove, change or swap with the next one to turn `a` into `b`
+pub fn edit_distance(a: &[Ingredient], b: &[Ingredient]) -> usize {
+    // d[i][j] is the distance between a[..i] and b[..j]
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() { row[0] = i; }
+    for (j, distance) in d[0].iter_mut().enumerate() { *distance = j; }
+    for (i, x) in a.iter().enumerate().map(|(i, x)| (i + 1, x)) {
+        for (j, y) in b.iter().enumerate().map(|(j, y)| (j + 1, y)) {
+            let cost = if x == y { 0 } else { 1 };
+            d[i][j] = min(min(d[i - 1][j] + 1, d[i][j - 1] + 1), d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && *x == b[j - 2] && a[i - 2] == *y {
+                d[i][j] = min(d[i][j], d[i - 2][j - 2] + 1);
             }
         }
     }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::Ingredient::*;
+
+    #[test]
+    fn swapped_layers_count_as_one_mistake() {
+        assert_eq!(edit_distance(&[Bread, Steak, Cheese, Bread], &[Bread, Cheese, Steak, Bread]), 1);
+        assert_eq!(edit_distance(&[Bread, Steak, Bread], &[Bread, Steak, Mayo, Bread]), 1);
+        assert_eq!(edit_distance(&[Bread, Steak, Bread], &[Bread, Steak, Bread]), 0);
+    }
 }
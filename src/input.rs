@@ -1,19 +1,34 @@
+use std::collections::HashMap;
+
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 
+use crate::bindings::KeyBindings;
+use crate::bot::Bot;
+use crate::is_headless;
+use crate::replay::Playback;
+use crate::settings::{InputMode, Settings};
+
 pub struct InputPlugin;
 
+/// A key of the game was pressed, see [`crate::ingredients::Ingredient::key`]
 pub struct KeyboardEvent(pub char);
+/// A key of the keyboard was released
 pub struct KeyboardReleaseEvent(pub char);
 
 // This plugin listens for keyboard input and converts the input into Actions
 // Actions can then be used as a resource in other systems to act on the player input.
+// Pressed keys are translated with the KeyBindings, menus use the keys of the keyboard.
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
         app
             .init_resource::<Actions>()
-            .add_system(process_input)
             .add_event::<KeyboardEvent>()
             .add_event::<KeyboardReleaseEvent>();
+
+        if is_headless(app) { return; }
+
+        app.add_system(process_input);
     }
 }
 
@@ -50,34 +65,171 @@ fn get_char(code: &KeyCode) -> Option<char> {
         KeyCode::X => Some('x'),
         KeyCode::Y => Some('y'),
         KeyCode::Z => Some('z'),
+        KeyCode::Key0 | KeyCode::Numpad0 => Some('0'),
+        KeyCode::Key1 | KeyCode::Numpad1 => Some('1'),
+        KeyCode::Key2 | KeyCode::Numpad2 => Some('2'),
+        KeyCode::Key3 | KeyCode::Numpad3 => Some('3'),
+        KeyCode::Key4 | KeyCode::Numpad4 => Some('4'),
+        KeyCode::Key5 | KeyCode::Numpad5 => Some('5'),
+        KeyCode::Key6 | KeyCode::Numpad6 => Some('6'),
+        KeyCode::Key7 | KeyCode::Numpad7 => Some('7'),
+        KeyCode::Key8 | KeyCode::Numpad8 => Some('8'),
+        KeyCode::Key9 | KeyCode::Numpad9 => Some('9'),
+        KeyCode::Comma => Some(','),
+        KeyCode::Period => Some('.'),
+        KeyCode::Semicolon => Some(';'),
+        KeyCode::Colon => Some(':'),
+        KeyCode::Slash => Some('/'),
+        KeyCode::Apostrophe => Some('\''),
         KeyCode::Back => Some('<'),
         KeyCode::Space | KeyCode::Return => Some(' '),
         _ => None,
     }
 }
 
+/// Keys which don't type a character, read from their [`KeyCode`] in every [`InputMode`]
+fn is_control(code: &KeyCode) -> bool {
+    matches!(code, KeyCode::Back | KeyCode::Space | KeyCode::Return)
+}
+
+/// Key of a character typed with the layout of the system
+fn typed_char(char: char) -> Option<char> {
+    let char = char.to_ascii_lowercase();
+    match char {
+        'a'..='z' | '0'..='9' | ',' | '.' | ';' | ':' | '/' | '\'' => Some(char),
+        _ => None,
+    }
+}
+
+/// Characters typed by the `pressed` keys of this frame.
+///
+/// A key held down types its character again and again, these repetitions are dropped:
+/// the ones of the `held` characters first, then the last ones.
+fn new_characters(mut received: Vec<char>, pressed: usize, held: &[char]) -> Vec<char> {
+    while received.len() > pressed {
+        match received.iter().position(|char| held.contains(char)) {
+            Some(i) => { received.remove(i); }
+            None => received.truncate(pressed),
+        }
+    }
+    received
+}
+
+/// Keys of the keyboard, read from the characters typed or from their place depending on the [`InputMode`]
+#[derive(SystemParam)]
+pub struct KeyboardKeys<'w, 's> {
+    characters: EventReader<'w, 's, ReceivedCharacter>,
+    keyboard_input: Res<'w, Input<KeyCode>>,
+    settings: Res<'w, Settings>,
+    /// Character typed by each key, for the menus which need its release
+    typed: Local<'s, HashMap<KeyCode, char>>,
+}
+
+impl<'w, 's> KeyboardKeys<'w, 's> {
+    /// Keys pressed during this frame
+    fn pressed(&mut self) -> Vec<char> {
+        let keyboard_input = &self.keyboard_input;
+        let mut pressed = vec![];
+        match self.settings.input_mode {
+            InputMode::KeyCodes => {
+                pressed.extend(keyboard_input.get_just_pressed().filter_map(get_char));
+            }
+            InputMode::Characters => {
+                let received: Vec<char> = self.characters.iter().filter_map(|c| typed_char(c.char)).collect();
+                let codes: Vec<&KeyCode> = keyboard_input.get_just_pressed().filter(|code| !is_control(code)).collect();
+                // The order of the keys pressed in the same frame is unknown, the character of a key is only
+                // remembered when it is the only one typed, for the menus which need its release
+                if let ([code], [char]) = (codes.as_slice(), received.as_slice()) {
+                    self.typed.insert(**code, *char);
+                }
+                let held: Vec<char> = keyboard_input
+                    .get_pressed()
+                    .filter(|code| !keyboard_input.just_pressed(**code))
+                    .filter_map(|code| self.typed.get(code).copied())
+                    .collect();
+                pressed.extend(new_characters(received, codes.len(), &held));
+                pressed.extend(keyboard_input.get_just_pressed().filter(|code| is_control(code)).filter_map(get_char));
+            }
+        }
+        pressed
+    }
+
+    fn key_char(&self, code: &KeyCode) -> Option<char> {
+        match self.settings.input_mode {
+            InputMode::Characters => self.typed.get(code).copied().or_else(|| get_char(code)),
+            InputMode::KeyCodes => get_char(code),
+        }
+    }
+}
+
 pub fn process_input(
     mut actions: ResMut<Actions>,
     mut events: EventWriter<KeyboardEvent>,
     mut released_events: EventWriter<KeyboardReleaseEvent>,
-    keyboard_input: Res<Input<KeyCode>>
+    mut keys: KeyboardKeys,
+    bindings: Res<KeyBindings>,
+    playback: Option<Res<Playback>>,
+    bot: Option<Res<Bot>>,
 ) {
-    for code in keyboard_input.get_just_pressed() {
-        if let Some(char) = get_char(code) {
+    let pressed = keys.pressed();
+
+    // Key presses come from the replay during a playback, or from the bot
+    if playback.is_none() && bot.is_none() {
+        for char in pressed.into_iter().filter_map(|key| bindings.game_key(key)) {
             events.send(KeyboardEvent(char));
         }
     }
-    for code in keyboard_input.get_just_released() {
-        if let Some(char) = get_char(code) {
+    for code in keys.keyboard_input.get_just_released() {
+        if let Some(char) = keys.key_char(code) {
             released_events.send(KeyboardReleaseEvent(char));
         }
     }
 
     actions.pressed.clear();
 
-    for code in keyboard_input.get_pressed() {
-        if let Some(char) = get_char(code) {
+    for code in keys.keyboard_input.get_pressed() {
+        if let Some(char) = keys.key_char(code) {
             actions.pressed.push(char);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_letters_are_lowercase_keys() {
+        assert_eq!(typed_char('A'), Some('a'));
+        assert_eq!(typed_char('7'), Some('7'));
+        assert_eq!(typed_char(';'), Some(';'));
+        assert_eq!(typed_char('é'), None);
+        assert_eq!(typed_char(' '), None);
+    }
+
+    #[test]
+    fn layout_punctuation_has_a_key_code() {
+        // Keys of the AZERTY preset
+        for (code, char) in [(KeyCode::Comma, ','), (KeyCode::Semicolon, ';'), (KeyCode::Colon, ':')] {
+            assert_eq!(get_char(&code), Some(char));
+            assert_eq!(typed_char(char), Some(char));
+        }
+    }
+
+    #[test]
+    fn control_keys_are_read_from_their_place() {
+        assert!(is_control(&KeyCode::Back));
+        assert!(is_control(&KeyCode::Return));
+        assert!(!is_control(&KeyCode::A));
+    }
+
+    #[test]
+    fn repeated_characters_are_dropped() {
+        // A held key repeating
+        assert_eq!(new_characters(vec!['a'], 0, &['a']), Vec::<char>::new());
+        assert_eq!(new_characters(vec!['a', 'b'], 1, &['a']), vec!['b']);
+        // The characters of the held keys aren't known
+        assert_eq!(new_characters(vec!['b', 'c'], 1, &[]), vec!['b']);
+        assert_eq!(new_characters(vec!['b', 'c'], 2, &[]), vec!['b', 'c']);
+    }
+}
@@ -0,0 +1,80 @@
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+/// Number of entries kept for each mode
+pub const LEADERBOARD_SIZE: usize = 10;
+pub const INITIALS_LENGTH: usize = 3;
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub initials: String,
+    pub score: i64,
+    pub burgers: u32,
+    pub longest_chain: i64,
+    /// Local date of the game, `YYYY-MM-DD`
+    pub date: String,
+}
+
+/// Best scores of a mode, sorted from the best one
+#[derive(Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    /// [`PkvStore`] key of the leaderboard of the mode called `name`
+    pub fn key(name: &str) -> String {
+        format!("leaderboard_{}", name.replace(' ', "_"))
+    }
+
+    /// Loads the leaderboard of the mode called `name`.
+    ///
+    /// Before leaderboards, only the best score was saved as a string under the name of the difficulty,
+    /// it becomes an anonymous entry.
+    pub fn load(pkv: &PkvStore, name: &str) -> Leaderboard {
+        if let Ok(leaderboard) = pkv.get::<Leaderboard>(&Leaderboard::key(name)) {
+            return leaderboard;
+        }
+        let mut leaderboard = Leaderboard::default();
+        if let Some(score) = pkv.get::<String>(name).ok().and_then(|s| s.parse::<i64>().ok()) {
+            if score > 0 {
+                leaderboard.insert(LeaderboardEntry {
+                    initials: "???".to_string(),
+                    score,
+                    burgers: 0,
+                    longest_chain: 0,
+                    date: String::new(),
+                });
+            }
+        }
+        leaderboard
+    }
+
+    pub fn save(&self, pkv: &mut PkvStore, name: &str) {
+        let _ = pkv.set(&Leaderboard::key(name), self);
+    }
+
+    pub fn best_score(&self) -> i64 {
+        self.entries.first().map(|e| e.score).unwrap_or(0)
+    }
+
+    /// Position a game with this score would take in the leaderboard, if any
+    pub fn rank(&self, score: i64) -> Option<usize> {
+        if score <= 0 { return None; }
+        let rank = self.entries.iter().position(|e| score > e.score).unwrap_or(self.entries.len());
+        if rank < LEADERBOARD_SIZE { Some(rank) } else { None }
+    }
+
+    /// Inserts the entry at its rank and returns it, or `None` if the score is too low
+    pub fn insert(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+        let rank = self.rank(entry.score)?;
+        self.entries.insert(rank, entry);
+        self.entries.truncate(LEADERBOARD_SIZE);
+        Some(rank)
+    }
+}
+
+/// Today's local date, `YYYY-MM-DD`
+pub fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
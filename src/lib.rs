@@ -6,41 +6,84 @@ use bevy_kira_audio::AudioPlugin;
 use bevy_pkv::PkvStore;
 use bevy_tweening::component_animator_system;
 
+use crate::achievements::AchievementsPlugin;
 use crate::audio::InternalAudioPlugin;
+use crate::bindings::BindingsPlugin;
+use crate::bot::BotPlugin;
 use crate::button::ButtonPlugin;
+use crate::campaign::CampaignPlugin;
 use crate::chef::ChefPlugin;
+use crate::daily::DailyPlugin;
+use crate::clock::ClockPlugin;
 use crate::cooking::CookingPlugin;
 use crate::customer::CustomerPlugin;
+use crate::difficulty::DifficultyPlugin;
 use crate::game_over::GameOverPlugin;
 use crate::input::InputPlugin;
-use crate::loading::LoadingPlugin;
+use crate::loading::{FontAssets, LoadingPlugin};
 use crate::order::OrderPlugin;
+use crate::pause::PausePlugin;
+use crate::profile::ProfilePlugin;
+use crate::replay::ReplayPlugin;
 use crate::restaurant::RestaurantPlugin;
+use crate::rng::RngPlugin;
 use crate::score::ScorePlugin;
+use crate::settings::SettingsPlugin;
+use crate::stats::StatsPlugin;
 use crate::title::TitlePlugin;
+use crate::tutorial::TutorialPlugin;
 use crate::tween::TweenPlugin;
 
-mod input;
-mod audio;
-mod loading;
+pub mod input;
+pub mod audio;
+pub mod loading;
 mod title;
 mod button;
-mod ingredients;
-mod cooking;
-mod order;
-mod restaurant;
-mod score;
+pub mod ingredients;
+pub mod cooking;
+pub mod order;
+pub mod restaurant;
+pub mod score;
 mod game_over;
-mod customer;
+pub mod customer;
 mod tween;
 mod chef;
+pub mod rng;
+pub mod replay;
+pub mod headless;
+pub mod bot;
+pub mod difficulty;
+pub mod leaderboard;
+pub mod stats;
+pub mod profile;
+pub mod achievements;
+pub mod clock;
+pub mod settings;
+pub mod bindings;
+pub mod daily;
+pub mod tutorial;
+pub mod campaign;
+mod pause;
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
-enum GameState {
+pub enum GameState {
     Loading,
     Cooking,
     TitleScreen,
     GameOver,
+    Profile,
+    Achievements,
+    /// Pushed on top of `Cooking`
+    Paused,
+    Settings,
+    /// Key bindings, opened from the settings
+    Controls,
+    /// Calendar of the daily challenges
+    Daily,
+    /// Days of the campaign
+    Campaign,
+    /// The menu book couldn't be loaded, its error is shown until the game is closed
+    MenuError,
 }
 
 #[derive(SystemLabel)]
@@ -50,25 +93,50 @@ pub enum Labels {
     UI,
 }
 
+/// When this resource is inserted before [`GamePlugin`], only the gameplay logic is added:
+/// no loading, audio, sprites, tweens, menus or saves (see [`headless::HeadlessApp`]).
+pub struct Headless;
+
+pub fn is_headless(app: &App) -> bool {
+    app.world.contains_resource::<Headless>()
+}
+
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_state(GameState::Loading)
-            .add_plugin(LoadingPlugin)
-            .add_plugin(AudioPlugin)
-            .add_plugin(TitlePlugin)
             .add_plugin(InputPlugin)
+            .add_plugin(ClockPlugin)
             .add_plugin(InternalAudioPlugin)
-            .add_plugin(TweenPlugin)
-            .add_plugin(ButtonPlugin)
             .add_plugin(OrderPlugin)
             .add_plugin(CookingPlugin)
             .add_plugin(RestaurantPlugin)
-            .add_plugin(ChefPlugin)
             .add_plugin(CustomerPlugin)
             .add_plugin(ScorePlugin)
+            .add_plugin(StatsPlugin)
+            .add_plugin(RngPlugin)
+            .add_plugin(BotPlugin)
+            .add_plugin(DifficultyPlugin)
+            .add_plugin(AchievementsPlugin)
+            .add_plugin(TutorialPlugin)
+            .add_plugin(CampaignPlugin)
+            .add_plugin(ReplayPlugin);
+
+        if is_headless(app) { return; }
+
+        app.add_plugin(LoadingPlugin)
+            .add_plugin(AudioPlugin)
+            .add_plugin(TitlePlugin)
+            .add_plugin(TweenPlugin)
+            .add_plugin(ButtonPlugin)
+            .add_plugin(ChefPlugin)
             .add_plugin(GameOverPlugin)
+            .add_plugin(ProfilePlugin)
+            .add_plugin(DailyPlugin)
+            .add_plugin(PausePlugin)
+            .add_plugin(SettingsPlugin)
+            .add_plugin(BindingsPlugin)
             .add_system(component_animator_system::<TextureAtlasSprite>)
             .insert_resource(PkvStore::new("yopox", "miam"));
     }
@@ -89,4 +157,31 @@ pub fn spawn_sprite<'w, 's, 'a>(
             transform: Transform::from_translation(position),
             ..Default::default()
         })
-}
\ No newline at end of file
+}
+
+/// Spawns a line of text anchored at its bottom left corner
+pub fn spawn_text<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    fonts: &FontAssets,
+    text: String,
+    position: Vec2,
+    color: Color,
+) -> EntityCommands<'w, 's, 'a> {
+    commands.spawn_bundle(Text2dBundle {
+        text: Text {
+            sections: vec![
+                TextSection {
+                    value: text,
+                    style: TextStyle {
+                        font: fonts.axg.clone(),
+                        font_size: 16.0,
+                        color,
+                    },
+                },
+            ],
+            alignment: TextAlignment::BOTTOM_LEFT,
+        },
+        transform: Transform::from_xyz(position.x, position.y, 3.),
+        ..Default::default()
+    })
+}
@@ -1,20 +1,228 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset, LoadState};
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 use bevy_kira_audio::AudioSource;
+use bevy_pkv::PkvStore;
 
-use crate::GameState;
+use crate::{GameState, spawn_sprite, spawn_text};
+use crate::campaign::Campaign;
+use crate::ingredients::{Menu, MenuBook};
 
 pub struct LoadingPlugin;
 
+/// [`PkvStore`] key of the name of the menu served, chosen on the title screen or with `--menu <name>`
+pub const MENU_CHOICE: &str = "menu";
+
+/// Path of [`MenuAssets::book`]
+const MENU_BOOK: &str = "menus/default.menus.ron";
+/// Path of [`MenuAssets::campaign`]
+const CAMPAIGN: &str = "menus/default.campaign.ron";
+
+/// Characters of a line of the error screen
+const LINE_LENGTH: usize = 36;
+
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_loading_state(
-            LoadingState::new(GameState::Loading)
-                .with_collection::<FontAssets>()
-                .with_collection::<AudioAssets>()
-                .with_collection::<TextureAssets>()
-                .continue_to_state(GameState::TitleScreen),
-        );
+        let error = MenuFileError::default();
+        app.add_asset::<MenuBook>()
+            .add_asset::<Campaign>()
+            .add_asset_loader(MenuLoader { error: error.clone() })
+            .add_asset_loader(CampaignLoader { error: error.clone() })
+            .insert_resource(error)
+            .add_loading_state(
+                LoadingState::new(GameState::Loading)
+                    .with_collection::<FontAssets>()
+                    .with_collection::<AudioAssets>()
+                    .with_collection::<TextureAssets>()
+                    .with_collection::<MenuAssets>()
+                    .continue_to_state(GameState::TitleScreen),
+            )
+            .add_system_set(SystemSet::on_update(GameState::Loading).with_system(check_menu_files))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Loading)
+                    .with_system(select_menu)
+                    .with_system(insert_campaign)
+            )
+            .add_system_set(SystemSet::on_enter(GameState::MenuError).with_system(show_menu_error));
+    }
+}
+
+/// Why the files of the `menus` folder couldn't be loaded
+#[derive(Clone, Default)]
+pub struct MenuFileError {
+    /// Errors of the files which were read, by path, written by their loaders
+    reasons: Arc<Mutex<HashMap<String, String>>>,
+    /// File shown on the error screen
+    failed: Option<&'static str>,
+}
+
+impl MenuFileError {
+    fn set(&self, load_context: &LoadContext, error: &anyhow::Error) {
+        let path = load_context.path().to_string_lossy().replace('\\', "/");
+        self.reasons.lock().unwrap().insert(path, error.to_string());
+    }
+}
+
+/// Loads `.menus.ron` files, rejecting invalid menus
+pub struct MenuLoader {
+    error: MenuFileError,
+}
+
+impl AssetLoader for MenuLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let book = MenuBook::from_bytes(bytes);
+            if let Err(e) = &book {
+                self.error.set(load_context, e);
+            }
+            load_context.set_default_asset(LoadedAsset::new(book?));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["menus.ron"]
+    }
+}
+
+/// Loads `.campaign.ron` files, rejecting invalid campaigns
+pub struct CampaignLoader {
+    error: MenuFileError,
+}
+
+impl AssetLoader for CampaignLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let campaign = Campaign::from_bytes(bytes);
+            if let Err(e) = &campaign {
+                self.error.set(load_context, e);
+            }
+            load_context.set_default_asset(LoadedAsset::new(campaign?));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["campaign.ron"]
+    }
+}
+
+/// The loading never ends if the menu book or the campaign can't be read, the error screen is shown instead
+fn check_menu_files(
+    asset_server: Res<AssetServer>,
+    fonts: Option<Res<FontAssets>>,
+    textures: Option<Res<TextureAssets>>,
+    mut error: ResMut<MenuFileError>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if fonts.is_none() || textures.is_none() { return; }
+    let failed = [MENU_BOOK, CAMPAIGN].into_iter().find(|path| asset_server.get_load_state(*path) == LoadState::Failed);
+    if let Some(path) = failed {
+        error.failed = Some(path);
+        state.set(GameState::MenuError).unwrap_or_default();
+    }
+}
+
+/// Serves the menu chosen by the player, or the first one of the book
+fn select_menu(
+    mut commands: Commands,
+    menus: Option<Res<MenuAssets>>,
+    books: Res<Assets<MenuBook>>,
+    pkv: Res<PkvStore>,
+) {
+    let book = match menus.and_then(|menus| books.get(&menus.book)) {
+        Some(book) => book,
+        None => return,
+    };
+    let menu: &Menu = match pkv.get::<String>(MENU_CHOICE) {
+        Ok(name) => book.get(&name).unwrap_or_else(|| {
+            warn!("There is no menu called \"{}\", \"{}\" is served instead", name, book.menus[0].name);
+            &book.menus[0]
+        }),
+        Err(_) => &book.menus[0],
+    };
+    commands.insert_resource(menu.clone());
+}
+
+/// The days of the campaign are read by its systems
+fn insert_campaign(
+    mut commands: Commands,
+    menus: Option<Res<MenuAssets>>,
+    campaigns: Res<Assets<Campaign>>,
+) {
+    if let Some(campaign) = menus.and_then(|menus| campaigns.get(&menus.campaign)) {
+        commands.insert_resource(campaign.clone());
+    }
+}
+
+fn show_menu_error(
+    mut commands: Commands,
+    error: Res<MenuFileError>,
+    textures: Res<TextureAssets>,
+    fonts: Res<FontAssets>,
+) {
+    let path = error.failed.unwrap_or(MENU_BOOK);
+    let reason = error.reasons.lock().unwrap().get(path).cloned()
+        .unwrap_or_else(|| "the file is missing or can't be read".to_string());
+
+    spawn_sprite(&mut commands, textures.background.clone(), Vec3::ZERO);
+    spawn_text(&mut commands, &fonts, "THE MENUS COULDN'T BE LOADED".to_string(), Vec2::new(16., 160.), Color::WHITE);
+    spawn_text(&mut commands, &fonts, format!("assets/{}:", path), Vec2::new(16., 140.), Color::WHITE);
+    for (i, line) in wrap(&reason, LINE_LENGTH).into_iter().enumerate() {
+        spawn_text(&mut commands, &fonts, line, Vec2::new(16., 124. - 12. * i as f32), Color::rgb(1., 0.85, 0.3));
+    }
+    spawn_text(&mut commands, &fonts, "fix the file and restart the game".to_string(), Vec2::new(16., 14.), Color::WHITE);
+}
+
+/// Splits `text` into lines of at most `length` characters, cutting between words when possible
+fn wrap(text: &str, length: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        if !line.is_empty() && line.chars().count() + 1 + word.len() > length {
+            lines.push(std::mem::take(&mut line));
+        }
+        while word.len() > length {
+            lines.push(word.drain(..length).collect());
+        }
+        if !line.is_empty() { line.push(' '); }
+        line.extend(word);
+    }
+    if !line.is_empty() { lines.push(line); }
+    lines
+}
+
+/// Files of the `menus` folder, collected by hand so that their paths are only written in
+/// [`MENU_BOOK`] and [`CAMPAIGN`], which the error screen shows
+pub struct MenuAssets {
+    pub book: Handle<MenuBook>,
+    pub campaign: Handle<Campaign>,
+}
+
+impl AssetCollection for MenuAssets {
+    fn create(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        MenuAssets {
+            book: asset_server.get_handle(MENU_BOOK),
+            campaign: asset_server.get_handle(CAMPAIGN),
+        }
+    }
+
+    fn load(world: &mut World) -> Vec<HandleUntyped> {
+        let asset_server = world.resource::<AssetServer>();
+        vec![asset_server.load_untyped(MENU_BOOK), asset_server.load_untyped(CAMPAIGN)]
     }
 }
 
@@ -55,6 +263,8 @@ pub struct AudioAssets {
     pub customer_happy: Handle<AudioSource>,
     #[asset(path = "audio/SFX/chalk.ogg")]
     pub chalk: Handle<AudioSource>,
+    #[asset(path = "audio/SFX/menu_warning.ogg")]
+    pub menu_warning: Handle<AudioSource>,
 }
 
 #[derive(AssetCollection)]
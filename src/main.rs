@@ -2,12 +2,21 @@ use bevy::DefaultPlugins;
 use bevy::math::Vec3;
 use bevy::prelude::{App, Camera2dBundle, Commands, Msaa, Transform, WindowDescriptor};
 use bevy::render::texture::ImageSettings;
+use bevy_pkv::PkvStore;
 use bevy_tweening::TweeningPlugin;
 
+use miam::bot::Bot;
+use miam::cooking::MadnessMode;
+use miam::difficulty::{DIFFICULTY_SETTINGS, Difficulty, DifficultyPreset, DifficultySettings, GameMode};
 use miam::GamePlugin;
+use miam::loading::MENU_CHOICE;
+use miam::replay::{Playback, Replay};
+use miam::rng::Seed;
 
 fn main() {
-    App::new()
+    let mut app = App::new();
+
+    app
         .insert_resource(ImageSettings::default_nearest())
         .insert_resource(Msaa { samples: 1 })
         .insert_resource(WindowDescriptor {
@@ -20,9 +29,64 @@ fn main() {
         .add_plugins(DefaultPlugins)
         .add_plugin(TweeningPlugin)
         .add_plugin(GamePlugin)
-        .add_startup_system(init)
+        .add_startup_system(init);
         // .add_startup_system(set_window_icon)
-        .run();
+
+    // A seed given with `--seed <n>` is kept for every game
+    if let Some(value) = arg_value("--seed").and_then(|s| s.parse::<u64>().ok()) {
+        app.insert_resource(Seed { value, locked: true });
+    }
+
+    // A replay given with `--replay <file>` is played as soon as the game is loaded
+    if let Some(path) = arg_value("--replay") {
+        match Replay::from_file(&path) {
+            Ok(replay) => { app.insert_resource(Playback::new(replay)); }
+            Err(e) => eprintln!("Couldn't read the replay {}: {}", path, e),
+        }
+    }
+
+    // With `--bot`, the bot plays game after game and logs how long it survived
+    if has_flag("--bot") {
+        let speed = arg_value("--bot-speed").and_then(|s| s.parse::<f32>().ok()).unwrap_or(4.);
+        let errors = arg_value("--bot-errors").and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.05);
+        let madness = has_flag("--madness");
+        app.insert_resource(Bot::new(speed, errors, false))
+            .insert_resource(MadnessMode(madness))
+            .insert_resource(GameMode::Classic(if madness { Difficulty::madness() } else { Difficulty::classic() }));
+    }
+
+    // Parameters of the custom difficulty can be read from a RON file with `--difficulty <file>`
+    if let Some(path) = arg_value("--difficulty") {
+        match Difficulty::from_file(&path) {
+            Ok(custom) => {
+                let mut pkv = app.world.resource_mut::<PkvStore>();
+                let settings = DifficultySettings { preset: DifficultyPreset::Custom, custom };
+                let _ = pkv.set(DIFFICULTY_SETTINGS, &settings);
+            }
+            Err(e) => eprintln!("Couldn't read the difficulty {}: {}", path, e),
+        }
+    }
+
+    // The menu served can be chosen by its name with `--menu <name>`, it is kept for the next games
+    if let Some(name) = arg_value("--menu") {
+        let mut pkv = app.world.resource_mut::<PkvStore>();
+        let _ = pkv.set(MENU_CHOICE, &name);
+    }
+
+    app.run();
+}
+
+fn has_flag(flag: &str) -> bool {
+    std::env::args().any(|arg| arg == flag)
+}
+
+/// Returns the value following `flag` in the command line arguments
+fn arg_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
 }
 
 // Sets the icon on windows and X11
@@ -1,14 +1,19 @@
+use std::marker::PhantomData;
 use std::time::Duration;
 
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy::utils::HashSet;
 
 use crate::{GameState, Labels};
-use crate::cooking::{ExpectingOrder, MadnessMode};
+use crate::clock::GameClock;
+use crate::cooking::ExpectingOrder;
 use crate::customer::CallNewCustomer;
+use crate::difficulty::GameMode;
 use crate::ingredients::{Ingredient, Menu};
 use crate::restaurant::{AddIngredientEvent, ShowOrderEvent};
-use crate::score::{LifeIcon, Score};
+use crate::rng::GameRng;
+use crate::score::Score;
 
 #[derive(Default)]
 pub struct Order {
@@ -29,14 +34,15 @@ pub struct BurgerFinishedEvent {
     pub correct: bool,
     pub size: usize,
     pub out_of_time: bool,
+    /// How close the burger is to the order, from 0 to 1 (see [`crate::ingredients::edit_distance`])
+    pub accuracy: f64,
+    /// The burger isn't correct, but close enough to earn part of the points without costing a life
+    pub partial: bool,
 }
 
 impl Plugin for OrderPlugin {
     fn build(&self, app: &mut App) {
-        let menu_reference = Menu::Uno;
-
-        app.insert_resource(menu_reference)
-            .init_resource::<Order>()
+        app.init_resource::<Order>()
             .init_resource::<MenuOnDisplay>()
             .add_event::<BurgerFinishedEvent>()
             .add_system_set(SystemSet::on_enter(GameState::Cooking)
@@ -56,24 +62,45 @@ impl Plugin for OrderPlugin {
 
 fn init_menu(
     menu: Res<Menu>,
-    madness_mode: Res<MadnessMode>,
+    mode: Res<GameMode>,
+    mut rng: ResMut<GameRng>,
     mut menu_on_display: ResMut<MenuOnDisplay>,
     mut ev_add_ingredient: EventWriter<AddIngredientEvent>,
 ) {
     menu_on_display.ingredients.clear();
     menu_on_display.ingredients_seen.clear();
-    for i in menu.basic_ingredients(madness_mode.0) {
+    let menu = mode.menu(&menu);
+    for i in menu.basic_ingredients(mode.difficulty().starting_menu_size(), &mut rng.menu) {
         ev_add_ingredient.send(AddIngredientEvent {
             ingredient: i,
+            replaced: None,
             timer: false
         });
     }
 }
 
+/// Everything a new order is generated from
+#[derive(SystemParam)]
+struct OrderMaker<'w, 's> {
+    menu: Res<'w, Menu>,
+    clock: Res<'w, GameClock>,
+    rng: ResMut<'w, GameRng>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> OrderMaker<'w, 's> {
+    fn make(&mut self, on_display: &Vec<Ingredient>) -> Order {
+        Order {
+            ingredients: self.menu.generate_order(on_display, &mut self.rng.orders),
+            creation_time: self.clock.elapsed(),
+        }
+    }
+}
+
 fn add_order(
     menu: Res<MenuOnDisplay>,
-    menu_ref: Res<Menu>,
-    time: Res<Time>,
+    mut maker: OrderMaker,
     mut commands: Commands,
     mut order: ResMut<Order>,
     mut ev_new_customer: EventReader<CallNewCustomer>,
@@ -82,8 +109,7 @@ fn add_order(
     if menu.ingredients.is_empty() { return; }
 
     for CallNewCustomer in ev_new_customer.iter() {
-        order.ingredients = menu_ref.generate_order(&menu.ingredients);
-        order.creation_time = time.time_since_startup();
+        *order = maker.make(&menu.ingredients);
         commands.insert_resource(ExpectingOrder(true));
         ev_show_order.send(ShowOrderEvent);
         break;
@@ -92,22 +118,21 @@ fn add_order(
 }
 
 fn receive_burger(
-    time: Res<Time>,
+    clock: Res<GameClock>,
     order: Res<Order>,
+    mode: Res<GameMode>,
     mut score: ResMut<Score>,
     mut ev_burger_sent: EventReader<BurgerFinishedEvent>,
-    mut life_icons: Query<(&LifeIcon, &mut TextureAtlasSprite)>,
 ) {
-    for &BurgerFinishedEvent { correct, size, out_of_time } in ev_burger_sent.iter() {
+    for &BurgerFinishedEvent { correct, size, accuracy, partial, .. } in ev_burger_sent.iter() {
+        let duration = clock.elapsed() - order.creation_time;
         if correct {
-            let duration = time.time_since_startup() - order.creation_time;
-            score.compute_on_success(duration.as_secs_f64(), size);
+            score.compute_on_success(duration.as_secs_f64(), size, mode.difficulty());
+            score.add_chain_bonus(mode.chain_bonus());
+        } else if partial {
+            score.compute_on_partial(duration.as_secs_f64(), order.ingredients.len(), accuracy, mode.difficulty());
         } else {
             score.compute_on_failure();
-            // Update life icons
-            for (LifeIcon(i), mut sprite) in life_icons.iter_mut() {
-                sprite.index = if *i >= score.lives { 1 } else { 0 };
-            }
         }
         break;
     }
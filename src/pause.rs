@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::window::WindowFocused;
+use bevy_kira_audio::{AudioChannel, AudioControl};
+use bevy_tweening::{Animator, AnimatorState};
+
+use crate::{GameState, spawn_text};
+use crate::audio::BgmChannel;
+use crate::bot::Bot;
+use crate::button::spawn_button;
+use crate::input::{KeyboardEvent, process_input};
+use crate::loading::{FontAssets, TextureAssets};
+use crate::replay::Playback;
+use crate::settings::Settings;
+
+pub struct PausePlugin;
+
+/// Height of the pause overlay, above everything drawn while cooking
+const OVERLAY_Z: f32 = 20.;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseState>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Cooking)
+                    .with_system(pause_on_escape)
+                    .with_system(pause_on_focus_loss)
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Paused)
+                    .with_system(freeze_animators)
+                    .with_system(show_pause_menu)
+            )
+            .add_system_set(SystemSet::on_update(GameState::Paused).with_system(handle_pause_input.after(process_input)))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Paused)
+                    .with_system(unfreeze_animators)
+                    .with_system(hide_pause_menu)
+            )
+            .add_system_set(SystemSet::on_resume(GameState::Cooking).with_system(resume_cooking));
+    }
+}
+
+#[derive(Default)]
+struct PauseState {
+    /// The music was playing when the game was paused
+    music: bool,
+    /// The game was paused because the window lost focus
+    unfocused: bool,
+}
+
+#[derive(Component)]
+struct PauseUi;
+
+/// Animators stopped by the pause, only these ones are played again when it ends
+#[derive(Component)]
+struct Frozen;
+
+fn pause_on_escape(
+    keyboard_input: Res<Input<KeyCode>>,
+    bot: Option<Res<Bot>>,
+    playback: Option<Res<Playback>>,
+    bgm: Res<AudioChannel<BgmChannel>>,
+    mut pause: ResMut<PauseState>,
+    mut state: ResMut<State<GameState>>,
+) {
+    // Demos and replays can't be paused, any key stops them
+    if bot.is_some() || playback.is_some() { return; }
+
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        pause_game(&mut state, &mut pause, &bgm, false);
+    }
+}
+
+fn pause_on_focus_loss(
+    mut ev_focus: EventReader<WindowFocused>,
+    settings: Res<Settings>,
+    bot: Option<Res<Bot>>,
+    playback: Option<Res<Playback>>,
+    bgm: Res<AudioChannel<BgmChannel>>,
+    mut pause: ResMut<PauseState>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let unfocused = ev_focus.iter().any(|event| !event.focused);
+    if !settings.auto_pause || bot.is_some() || playback.is_some() { return; }
+
+    if unfocused {
+        pause_game(&mut state, &mut pause, &bgm, true);
+    }
+}
+
+fn pause_game(state: &mut State<GameState>, pause: &mut PauseState, bgm: &AudioChannel<BgmChannel>, unfocused: bool) {
+    if state.push(GameState::Paused).is_ok() {
+        pause.unfocused = unfocused;
+        pause.music = bgm.is_playing_sound();
+        if pause.music { bgm.pause(); }
+    }
+}
+
+fn handle_pause_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    pause: Res<PauseState>,
+    bgm: Res<AudioChannel<BgmChannel>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) || keyboard_input.just_pressed(KeyCode::Space) {
+        state.pop().unwrap_or_default();
+    } else if keyboard_input.just_pressed(KeyCode::Q) {
+        if pause.music { bgm.resume(); }
+        // Leaves the cooking state too
+        state.replace(GameState::TitleScreen).unwrap_or_default();
+    }
+}
+
+/// The [`crate::clock::GameClock`] didn't advance during the pause, so it doesn't count against the player
+fn resume_cooking(
+    pause: Res<PauseState>,
+    bgm: Res<AudioChannel<BgmChannel>>,
+    mut keyboard_events: ResMut<Events<KeyboardEvent>>,
+) {
+    // The key which ended the pause must not reach the burger
+    keyboard_events.clear();
+    if pause.music { bgm.resume(); }
+}
+
+fn freeze_animators(
+    mut commands: Commands,
+    mut transforms: Query<(Entity, &mut Animator<Transform>)>,
+    mut sprites: Query<(Entity, &mut Animator<Sprite>)>,
+    mut atlas_sprites: Query<(Entity, &mut Animator<TextureAtlasSprite>)>,
+    mut texts: Query<(Entity, &mut Animator<Text>)>,
+) {
+    freeze(&mut commands, &mut transforms);
+    freeze(&mut commands, &mut sprites);
+    freeze(&mut commands, &mut atlas_sprites);
+    freeze(&mut commands, &mut texts);
+}
+
+fn freeze<T: Component>(commands: &mut Commands, animators: &mut Query<(Entity, &mut Animator<T>)>) {
+    for (entity, mut animator) in animators.iter_mut() {
+        if animator.state == AnimatorState::Playing {
+            animator.state = AnimatorState::Paused;
+            commands.entity(entity).insert(Frozen);
+        }
+    }
+}
+
+fn unfreeze_animators(
+    mut commands: Commands,
+    frozen: Query<Entity, With<Frozen>>,
+    mut transforms: Query<&mut Animator<Transform>, With<Frozen>>,
+    mut sprites: Query<&mut Animator<Sprite>, With<Frozen>>,
+    mut atlas_sprites: Query<&mut Animator<TextureAtlasSprite>, With<Frozen>>,
+    mut texts: Query<&mut Animator<Text>, With<Frozen>>,
+) {
+    transforms.for_each_mut(|mut animator| animator.state = AnimatorState::Playing);
+    sprites.for_each_mut(|mut animator| animator.state = AnimatorState::Playing);
+    atlas_sprites.for_each_mut(|mut animator| animator.state = AnimatorState::Playing);
+    texts.for_each_mut(|mut animator| animator.state = AnimatorState::Playing);
+    for entity in frozen.iter() {
+        commands.entity(entity).remove::<Frozen>();
+    }
+}
+
+fn show_pause_menu(
+    mut commands: Commands,
+    pause: Res<PauseState>,
+    textures: Res<TextureAssets>,
+    fonts: Res<FontAssets>,
+) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0., 0., 0., 0.6),
+                custom_size: Some(Vec2::new(320., 180.)),
+                anchor: Anchor::BottomLeft,
+                ..Default::default()
+            },
+            transform: Transform::from_xyz(0., 0., OVERLAY_Z),
+            ..Default::default()
+        })
+        .insert(PauseUi);
+
+    let (title, x) = if pause.unfocused { ("press SPACE to resume", 112.) } else { ("PAUSE", 146.) };
+    spawn_text(&mut commands, &fonts, title.to_string(), Vec2::ZERO, Color::WHITE)
+        .insert(Transform::from_xyz(x, 110., OVERLAY_Z + 1.))
+        .insert(PauseUi);
+
+    let options = [(' ', "resume", 80.), ('q', "quit to title", 60.)];
+    for (letter, name, y) in options {
+        let (button, _) = spawn_button(&mut commands, Vec2::new(120., y), letter, &textures, &fonts, false);
+        commands
+            .entity(button)
+            .insert(Transform::from_xyz(120., y, OVERLAY_Z + 1.))
+            .insert(PauseUi);
+        spawn_text(&mut commands, &fonts, name.to_string(), Vec2::ZERO, Color::WHITE)
+            .insert(Transform::from_xyz(136., y + 2., OVERLAY_Z + 1.))
+            .insert(PauseUi);
+    }
+}
+
+fn hide_pause_menu(
+    mut commands: Commands,
+    entities: Query<Entity, With<PauseUi>>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
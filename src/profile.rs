@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_pkv::PkvStore;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{GameState, spawn_sprite, spawn_text};
+use crate::button::spawn_button;
+use crate::difficulty::GameMode;
+use crate::ingredients::Ingredient;
+use crate::input::KeyboardReleaseEvent;
+use crate::leaderboard::today;
+use crate::loading::{FontAssets, TextureAssets};
+use crate::replay::ReplayRecorder;
+use crate::stats::RunStats;
+
+pub struct ProfilePlugin;
+
+/// [`PkvStore`] key of the [`Profile`]
+pub const PROFILE: &str = "profile";
+/// Number of days kept in [`Profile::days`]
+const PROFILE_DAYS: usize = 28;
+/// Number of ingredients listed on the stats page
+const FAVORITE_INGREDIENTS: usize = 10;
+
+#[derive(Component)]
+struct ProfileUi;
+
+/// Statistics of every game played on this computer
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub games: u32,
+    pub burgers: u32,
+    pub ingredients_placed: HashMap<Ingredient, u32>,
+    /// Time spent cooking, in seconds
+    pub time_cooked: f64,
+    /// Games played in each mode, by name
+    pub games_per_mode: HashMap<String, u32>,
+    /// Games of the last days, oldest first
+    #[serde(default)]
+    pub days: Vec<DayRecord>,
+}
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct DayRecord {
+    /// Local date, `YYYY-MM-DD`
+    pub date: String,
+    pub games: u32,
+    pub burgers: u32,
+    pub time_cooked: f64,
+}
+
+impl DayRecord {
+    /// Burgers served per minute of cooking
+    pub fn burgers_per_minute(&self) -> Option<f64> {
+        if self.time_cooked <= 0. { return None; }
+        Some(self.burgers as f64 * 60. / self.time_cooked)
+    }
+}
+
+impl Profile {
+    pub fn load(pkv: &PkvStore) -> Profile {
+        pkv.get::<Profile>(PROFILE).unwrap_or_default()
+    }
+
+    pub fn save(&self, pkv: &mut PkvStore) {
+        let _ = pkv.set(PROFILE, self);
+    }
+
+    /// Adds a finished game played on `date`
+    pub fn record(&mut self, stats: &RunStats, mode: &str, date: String) {
+        self.games += 1;
+        self.burgers += stats.burgers;
+        self.time_cooked += stats.time_cooked;
+        for (ingredient, count) in stats.placed_ingredients.iter() {
+            *self.ingredients_placed.entry(*ingredient).or_insert(0) += count;
+        }
+        *self.games_per_mode.entry(mode.to_string()).or_insert(0) += 1;
+
+        if self.days.last().map_or(true, |day| day.date != date) {
+            self.days.push(DayRecord { date, ..DayRecord::default() });
+        }
+        let day = self.days.last_mut().unwrap();
+        day.games += 1;
+        day.burgers += stats.burgers;
+        day.time_cooked += stats.time_cooked;
+
+        if self.days.len() > PROFILE_DAYS {
+            self.days.drain(..self.days.len() - PROFILE_DAYS);
+        }
+    }
+
+    /// Mode with the most games
+    pub fn favorite_mode(&self) -> Option<&str> {
+        self.games_per_mode.iter()
+            .max_by_key(|(mode, games)| (**games, std::cmp::Reverse(*mode)))
+            .map(|(mode, _)| mode.as_str())
+    }
+
+    /// Most placed ingredients with their counts, from the most placed one
+    pub fn favorite_ingredients(&self, amount: usize) -> Vec<(Ingredient, u32)> {
+        let mut ingredients: Vec<(Ingredient, u32)> = self.ingredients_placed.iter().map(|(i, n)| (*i, *n)).collect();
+        ingredients.sort_by_key(|(ingredient, count)| (std::cmp::Reverse(*count), *ingredient as usize));
+        ingredients.truncate(amount);
+        ingredients
+    }
+
+    /// Sum of the days of the week which ended `weeks_ago` weeks before `today` (0 is the last 7 days)
+    pub fn week(&self, today: &str, weeks_ago: i64) -> DayRecord {
+        let mut week = DayRecord::default();
+        let today = match NaiveDate::parse_from_str(today, "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => return week,
+        };
+        for day in self.days.iter() {
+            let date = match NaiveDate::parse_from_str(&day.date, "%Y-%m-%d") {
+                Ok(date) => date,
+                Err(_) => continue,
+            };
+            if (today - date).num_days().div_euclid(7) == weeks_ago {
+                week.games += day.games;
+                week.burgers += day.burgers;
+                week.time_cooked += day.time_cooked;
+            }
+        }
+        week
+    }
+}
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        // Games quit from the pause menu count too, they leave the cooking state without a game over
+        app.add_system_set(SystemSet::on_exit(GameState::Cooking).with_system(record_game))
+            .add_system_set(SystemSet::on_enter(GameState::Profile).with_system(setup_profile))
+            .add_system_set(SystemSet::on_update(GameState::Profile).with_system(handle_input))
+            .add_system_set(SystemSet::on_exit(GameState::Profile).with_system(cleanup_profile));
+    }
+}
+
+fn record_game(
+    stats: Res<RunStats>,
+    mode: Res<GameMode>,
+    recorder: Res<ReplayRecorder>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    // Games played by the bot or watched from a replay are not the player's
+    if !recorder.is_player_game() { return; }
+
+    let mut profile = Profile::load(&pkv);
+    profile.record(&stats, mode.name(), today());
+    profile.save(&mut pkv);
+}
+
+fn setup_profile(
+    mut commands: Commands,
+    pkv: Res<PkvStore>,
+    textures: Res<TextureAssets>,
+    fonts: Res<FontAssets>,
+) {
+    let profile = Profile::load(&pkv);
+    let today = today();
+
+    spawn_sprite(&mut commands, textures.background.clone(), Vec3::ZERO).insert(ProfileUi);
+    spawn_sprite(&mut commands, textures.menu.clone(), Vec3::new(8., 8., 0.75)).insert(ProfileUi);
+    spawn_sprite(&mut commands, textures.counter.clone(), Vec3::new(0., 0., 0.5)).insert(ProfileUi);
+
+    // Most placed ingredients on the chalkboard
+    spawn_text(&mut commands, &fonts, "MOST PLACED".to_string(), Vec2::new(16., 158.), Color::WHITE).insert(ProfileUi);
+    for (i, (ingredient, count)) in profile.favorite_ingredients(FAVORITE_INGREDIENTS).into_iter().enumerate() {
+        let line = format!("{:.<10}{:.>4}", ingredient.name().to_uppercase(), count);
+        spawn_text(&mut commands, &fonts, line, Vec2::new(16., 144. - 12. * i as f32), Color::WHITE).insert(ProfileUi);
+    }
+
+    let hours = (profile.time_cooked / 3600.) as u64;
+    let minutes = (profile.time_cooked / 60.) as u64 % 60;
+    let mut lines = vec![
+        ("GAMES PLAYED".to_string(), profile.games.to_string()),
+        ("BURGERS SERVED".to_string(), profile.burgers.to_string()),
+        ("TIME COOKED".to_string(), format!("{}H{:02}", hours, minutes)),
+        ("FAVORITE MODE".to_string(), profile.favorite_mode().map_or("-".to_string(), |mode| mode.to_uppercase())),
+    ];
+    // Progress of the last weeks
+    for (weeks_ago, name) in [(0, "THIS WEEK"), (1, "LAST WEEK")] {
+        let week = profile.week(&today, weeks_ago);
+        lines.push((format!("{} BURGERS", name), week.burgers.to_string()));
+        lines.push((
+            format!("{} PER MIN", name),
+            week.burgers_per_minute().map_or("-".to_string(), |b| format!("{:.1}", b)),
+        ));
+    }
+
+    spawn_text(&mut commands, &fonts, "YOUR KITCHEN".to_string(), Vec2::new(112., 158.), Color::WHITE).insert(ProfileUi);
+    for (i, (label, value)) in lines.into_iter().enumerate() {
+        let line = format!("{:.<20}{:.>8}", label, value);
+        spawn_text(&mut commands, &fonts, line, Vec2::new(112., 140. - 12. * i as f32), Color::WHITE).insert(ProfileUi);
+    }
+
+    let (button, _) = spawn_button(&mut commands, Vec2::new(124., 12.), ' ', &textures, &fonts, false);
+    commands.entity(button).insert(ProfileUi);
+    spawn_text(&mut commands, &fonts, "back".to_string(), Vec2::new(140., 14.), Color::WHITE).insert(ProfileUi);
+}
+
+fn handle_input(
+    mut events: EventReader<KeyboardReleaseEvent>,
+    mut state: ResMut<State<GameState>>,
+) {
+    for KeyboardReleaseEvent(key) in events.iter() {
+        if *key == ' ' || *key == '<' {
+            state.set(GameState::TitleScreen).unwrap_or_default();
+        }
+    }
+}
+
+fn cleanup_profile(
+    mut commands: Commands,
+    entities: Query<Entity, With<ProfileUi>>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
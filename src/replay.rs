@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+use crate::{GameState, is_headless, Labels};
+use crate::bot::Bot;
+use crate::clock::GameClock;
+use crate::cooking::MadnessMode;
+use crate::difficulty::{Difficulty, GameMode};
+use crate::input::{KeyboardEvent, process_input};
+use crate::rng::Seed;
+use crate::tutorial::Tutorial;
+
+pub struct ReplayPlugin;
+
+/// [`PkvStore`] key of the last recorded game
+pub const LAST_REPLAY: &str = "last_replay";
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayRecorder>()
+            .add_system_set(SystemSet::on_update(GameState::TitleScreen).with_system(start_playback))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Cooking)
+                    .before(Labels::LogicSender)
+                    .with_system(start_recording)
+                    .with_system(rewind_playback),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Cooking)
+                    .before(Labels::LogicSender)
+                    // Keys are stamped with the frame in which the cooking loop reads them
+                    .with_system(record_input.after(process_input))
+                    .with_system(play_input),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Cooking).with_system(stop_playback));
+
+        if is_headless(app) { return; }
+
+        app.add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(save_replay));
+    }
+}
+
+/// Everything needed to play a game again
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub madness: bool,
+    pub events: Vec<ReplayEvent>,
+    /// Replays recorded before game modes existed were played in classic or madness
+    #[serde(default)]
+    pub mode: Option<GameMode>,
+    /// Duration of each frame on the [`GameClock`], in nanoseconds.
+    ///
+    /// The game is played again frame by frame with the same durations, so it ends exactly the same way.
+    /// Replays recorded without them are only played at the same times.
+    #[serde(default)]
+    pub frames: Vec<u32>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct ReplayEvent {
+    /// Frame of the key press on the [`GameClock`]
+    pub frame: u32,
+    /// Seconds elapsed since the start of the game, on the [`GameClock`]
+    pub time: f64,
+    pub key: char,
+}
+
+impl Replay {
+    pub fn from_file(path: &str) -> anyhow::Result<Replay> {
+        Ok(ron::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+#[derive(Default)]
+pub struct ReplayRecorder {
+    replay: Replay,
+    /// Games played from a replay or by the bot aren't saved
+    save: bool,
+}
+
+impl ReplayRecorder {
+    /// The game was played on the keyboard, not by a replay or the bot
+    pub fn is_player_game(&self) -> bool {
+        self.save
+    }
+
+    /// Game recorded since the last start of cooking
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+}
+
+/// While this resource exists, [`KeyboardEvent`]s come from the replay instead of the keyboard.
+///
+/// Inserting it on the title screen starts the replay.
+pub struct Playback {
+    replay: Replay,
+    next: usize,
+    /// Seed of the player, given back when the replay ends
+    player_seed: Option<Seed>,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Playback { replay, next: 0, player_seed: None }
+    }
+
+    /// Recorded duration of `frame`, if the replay has one
+    pub fn frame_delta(&self, frame: u32) -> Option<Duration> {
+        self.replay.frames.get(frame as usize).map(|&nanos| Duration::from_nanos(nanos as u64))
+    }
+}
+
+fn start_playback(
+    mut commands: Commands,
+    mut state: ResMut<State<GameState>>,
+    seed: Res<Seed>,
+    playback: Option<ResMut<Playback>>,
+) {
+    if let Some(mut playback) = playback {
+        if playback.player_seed.is_none() {
+            playback.player_seed = Some(seed.clone());
+        }
+        commands.insert_resource(Seed { value: playback.replay.seed, locked: false });
+        commands.insert_resource(MadnessMode(playback.replay.madness));
+        let mode = playback.replay.mode.clone().unwrap_or_else(|| {
+            GameMode::Classic(if playback.replay.madness { Difficulty::madness() } else { Difficulty::classic() })
+        });
+        // The guide of a tutorial is only shown while this resource exists
+        if let GameMode::Tutorial(_) = mode {
+            commands.insert_resource(Tutorial::default());
+        }
+        commands.insert_resource(mode);
+        state.set(GameState::Cooking).unwrap_or_default();
+    }
+}
+
+fn start_recording(
+    seed: Res<Seed>,
+    madness: Res<MadnessMode>,
+    mode: Res<GameMode>,
+    playback: Option<Res<Playback>>,
+    bot: Option<Res<Bot>>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    *recorder = ReplayRecorder {
+        replay: Replay {
+            seed: seed.value,
+            madness: madness.0,
+            events: vec![],
+            mode: Some(mode.clone()),
+            frames: vec![],
+        },
+        save: playback.is_none() && bot.is_none(),
+    };
+}
+
+fn record_input(
+    clock: Res<GameClock>,
+    mut recorder: ResMut<ReplayRecorder>,
+    mut input: EventReader<KeyboardEvent>,
+) {
+    let frame = clock.frame();
+    let elapsed = clock.elapsed().as_secs_f64();
+    recorder.replay.frames.push(clock.delta().as_nanos() as u32);
+    for &KeyboardEvent(key) in input.iter() {
+        recorder.replay.events.push(ReplayEvent { frame, time: elapsed, key });
+    }
+}
+
+fn rewind_playback(playback: Option<ResMut<Playback>>) {
+    if let Some(mut playback) = playback {
+        playback.next = 0;
+    }
+}
+
+fn play_input(
+    clock: Res<GameClock>,
+    playback: Option<ResMut<Playback>>,
+    mut events: EventWriter<KeyboardEvent>,
+) {
+    if let Some(mut playback) = playback {
+        let exact = !playback.replay.frames.is_empty();
+        let elapsed = clock.elapsed().as_secs_f64();
+        while let Some(&event) = playback.replay.events.get(playback.next) {
+            let due = if exact { event.frame <= clock.frame() } else { event.time <= elapsed };
+            if !due { break; }
+            events.send(KeyboardEvent(event.key));
+            playback.next += 1;
+        }
+    }
+}
+
+fn stop_playback(mut commands: Commands, playback: Option<Res<Playback>>) {
+    if let Some(seed) = playback.and_then(|playback| playback.player_seed.clone()) {
+        commands.insert_resource(seed);
+    }
+    commands.remove_resource::<Playback>();
+}
+
+fn save_replay(
+    recorder: Res<ReplayRecorder>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    if !recorder.save { return; }
+
+    let _ = pkv.set(LAST_REPLAY, &recorder.replay);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = format!("replays/{}-{}.ron", timestamp, recorder.replay.seed);
+        let saved = std::fs::create_dir_all("replays")
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Ok(ron::ser::to_string(&recorder.replay)?))
+            .and_then(|replay| Ok(std::fs::write(&path, replay)?));
+        match saved {
+            Ok(_) => info!("Replay saved to {}", path),
+            Err(e) => warn!("Couldn't save the replay: {}", e),
+        }
+    }
+}
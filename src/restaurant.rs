@@ -3,36 +3,69 @@ use std::time::Duration;
 
 use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
-use bevy::sprite::Anchor;
+use bevy::sprite::{Anchor, MaterialMesh2dBundle};
 use bevy_tweening::{Animator, Delay, EaseFunction, Tween, TweenCompleted, TweeningType};
-use rand::{Rng, thread_rng};
+use rand::Rng;
 use rand::prelude::SliceRandom;
 
-use crate::{GameState, Labels, spawn_sprite, tween};
+use crate::{GameState, is_headless, Labels, spawn_sprite, tween};
 use crate::audio::{PlaySfxEvent, SFX};
+use crate::bindings::{Binding, KeyBindings};
 use crate::button::{Letter, PreventButtonUpdate, spawn_button};
-use crate::cooking::CurrentBurger;
+use crate::clock::GameClock;
+use crate::cooking::{CurrentBurger, OrderNumber};
+use crate::difficulty::{Difficulty, GameMode};
 use crate::ingredients::{Ingredient, Menu};
 use crate::loading::{FontAssets, TextureAssets};
 use crate::order::{BurgerFinishedEvent, MenuOnDisplay, Order};
+use crate::rng::GameRng;
 use crate::tween::{EV_ALLOW_BUTTON_UPDATE, EV_CHALK, EV_DELETE, EV_NOTHING, TransformAtlasSpriteAlphaLens, tween_opacity, tween_text_opacity, TWEEN_TIME};
 
 /// Flow of the restaurant:
 /// 1. [`crate::cooking::start_cooking`] -> Sends [`crate::customer::CallNewCustomer`] to call the first customer
 /// 2. [`crate::customer::customer_enter`] -> Listens to [`crate::customer::CallNewCustomer`] and make the customer appears
 /// 3. [`crate::order::add_order`] -> Listens to [`crate::customer::CallNewCustomer`], generates the order of the customer and sends [`ShowOrderEvent`]
-/// 4. [`show_order`] -> Shows the order
+/// 4. [`show_order`] -> Shows the order, [`crate::customer::customer_wait`] -> Starts the patience timer of the customer
 /// 5. [`crate::cooking::send_order`] -> The user sends an order and the event [`BurgerFinishedEvent`] is sent
+///    ([`crate::customer::watch_customer_waiting_time`] sends it when the customer is out of patience)
 ///     - [`crate::cooking::display_streak_or_miss`] -> Listens to [`BurgerFinishedEvent`] and displays GUI
 ///     - [`crate::cooking::animate_burger`] -> Listens to [`BurgerFinishedEvent`] and animates the burger
+///     - [`crate::cooking::clear_burger`] -> Listens to [`BurgerFinishedEvent`] and empties the plate
 ///     - [`hide_order`] -> Listens to [`BurgerFinishedEvent`] and hide the current order
 ///     - [`crate::order::receive_burger`] -> Listens to [`BurgerFinishedEvent`], updates the score
-///     - [`crate::customer::customer_exit`] -> Listens to [`BurgerFinishedEvent`], make the customer exit and sends [`TweenCompleted { _, crate::tween::EV_CUSTOMER_EXITED }`] when customer has exited
-/// 6. [`crate::customer::watch_customer_exited`] -> Sends [`crate::customer::CallNewCustomer`] or sets State to [`crate::GameState::GameOver`]
+///     - [`crate::customer::customer_exit`] -> Listens to [`BurgerFinishedEvent`] and make the customer exit
+///     - [`crate::customer::customer_leave`] -> Listens to [`BurgerFinishedEvent`] and starts the exit timer of the customer
+/// 6. [`crate::customer::watch_customer_exited`] -> When the customer has exited, sends [`crate::customer::CallNewCustomer`] or sets State to [`crate::GameState::GameOver`]
+///
+/// Systems which only display things are not added in headless mode (see [`crate::Headless`]).
 pub struct RestaurantPlugin;
 
 impl Plugin for RestaurantPlugin {
     fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Cooking)
+                .after(Labels::LogicSender)
+                .after(Labels::LogicReceiver)
+                .before(Labels::UI)
+                .with_system(add_ingredient_watcher)
+                .with_system(add_ingredient_to_menu),
+        )
+        .add_system_set(
+            SystemSet::on_enter(GameState::Cooking)
+                .before(Labels::LogicSender)
+                .with_system(reset_add_ingredient_timer),
+        )
+        .insert_resource(AddIngredientTimer(Timer::new(
+            Difficulty::default().menu_interval(0),
+            false,
+        )))
+        .init_resource::<NextMenuChange>()
+        .add_event::<ShowOrderEvent>()
+        .add_event::<AddIngredientEvent>()
+        .add_event::<ShowIngredientEvent>();
+
+        if is_headless(app) { return; }
+
         app.add_system_set(
             SystemSet::on_enter(GameState::Cooking)
                 .label(Labels::UI)
@@ -44,21 +77,14 @@ impl Plugin for RestaurantPlugin {
                 .after(Labels::LogicSender)
                 .after(Labels::LogicReceiver)
                 .with_system(update_arrow)
+                .with_system(update_menu_countdown)
+                .with_system(highlight_next_change)
                 .with_system(show_order)
                 .with_system(hide_order)
-                .with_system(add_ingredient_watcher)
-                .with_system(add_ingredient_to_menu)
                 .with_system(show_menu)
                 .with_system(chalk),
         )
-        .add_system_set(SystemSet::on_exit(GameState::Cooking).with_system(clean_restaurant))
-        .insert_resource(AddIngredientTimer(Timer::new(
-            Duration::from_secs(10),
-            true,
-        )))
-        .add_event::<ShowOrderEvent>()
-        .add_event::<AddIngredientEvent>()
-        .add_event::<ShowIngredientEvent>();
+        .add_system_set(SystemSet::on_exit(GameState::Cooking).with_system(clean_restaurant));
     }
 }
 
@@ -74,8 +100,9 @@ pub struct ShowIngredientEvent {
 #[derive(Component)]
 struct CurrentOrderIngredient;
 
+/// Points at the next ingredient of the order
 #[derive(Component)]
-struct Arrow;
+pub struct Arrow;
 
 #[derive(Component)]
 struct RestaurantUi;
@@ -83,7 +110,9 @@ struct RestaurantUi;
 fn init_restaurant(
     mut commands: Commands,
     textures: Res<TextureAssets>,
-    fonts: Res<FontAssets>
+    fonts: Res<FontAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
     spawn_sprite(&mut commands, textures.background.clone(), Vec3::ZERO).insert(RestaurantUi);
     spawn_sprite(&mut commands, textures.bubble.clone(), Vec3::new(184., 64., 0.5)).insert(RestaurantUi);
@@ -111,6 +140,100 @@ fn init_restaurant(
             ..Default::default()
         })
         .insert(RestaurantUi);
+
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text {
+                sections: vec![TextSection {
+                    value: String::new(),
+                    style: TextStyle {
+                        font: fonts.axg.clone(),
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                }],
+                ..Default::default()
+            },
+            transform: Transform::from_xyz(104., 176., 1.),
+            ..Default::default()
+        })
+        .insert(MenuCountdown)
+        .insert(RestaurantUi);
+
+    commands
+        .spawn_bundle(MaterialMesh2dBundle {
+            mesh: meshes
+                .add(shape::Quad::new(Vec2::new(1., 3.)).into())
+                .into(),
+            material: materials.add(ColorMaterial::from(Color::WHITE)),
+            transform: Transform::from_translation(Vec3::new(104. + COUNTDOWN_BAR_SIZE / 2., 164., 1.))
+                .with_scale(Vec3::new(COUNTDOWN_BAR_SIZE, 1., 1.)),
+            ..default()
+        })
+        .insert(MenuCountdown)
+        .insert(RestaurantUi);
+}
+
+#[derive(Component)]
+struct MenuCountdown;
+
+const COUNTDOWN_BAR_SIZE: f32 = 64.;
+
+fn update_menu_countdown(
+    add_ingredient_timer: Res<AddIngredientTimer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut texts: Query<&mut Text, With<MenuCountdown>>,
+    mut bars: Query<(&mut Transform, &Handle<ColorMaterial>), With<MenuCountdown>>,
+) {
+    let remaining = add_ingredient_timer.remaining();
+    let seconds = remaining.as_secs_f32().ceil() as u32;
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = format!("NEW DISH IN {}", seconds);
+    }
+
+    // The bar empties from the right and turns red just before the change
+    let left = add_ingredient_timer.0.percent_left();
+    for (mut transform, material) in bars.iter_mut() {
+        transform.scale.x = COUNTDOWN_BAR_SIZE * left;
+        transform.translation.x = 104. + COUNTDOWN_BAR_SIZE * left / 2.;
+        if let Some(material) = materials.get_mut(material) {
+            let color = if remaining <= MENU_WARNING { Color::rgb(0.9, 0.3, 0.3) } else { Color::WHITE };
+            if material.color != color { material.color = color; }
+        }
+    }
+}
+
+/// The menu item which is about to be replaced blinks
+fn highlight_next_change(
+    next_change: Res<NextMenuChange>,
+    add_ingredient_timer: Res<AddIngredientTimer>,
+    mut highlighted: Local<Option<u8>>,
+    mut texts: Query<(&mut Text, &CurrentMenuIngredient)>,
+) {
+    let mut set_color = |position: u8, red: bool| {
+        for (mut text, _) in texts.iter_mut().filter(|(_, &CurrentMenuIngredient(i))| i == position) {
+            let color = text.sections[0].style.color;
+            let new_color = if red { Color::rgba(0.9, 0.3, 0.3, color.a()) } else { Color::rgba(1., 1., 1., color.a()) };
+            if color != new_color {
+                text.sections[0].style.color = new_color;
+            }
+        }
+    };
+
+    if next_change.is_changed() {
+        if let Some(position) = highlighted.take() {
+            set_color(position, false);
+        }
+        if let Some(MenuChange { replaced: Some(position), .. }) = next_change.0 {
+            *highlighted = Some(position as u8);
+        }
+    }
+
+    if let Some(position) = *highlighted {
+        let remaining = add_ingredient_timer.remaining();
+        let blink = remaining <= MENU_HIGHLIGHT && (remaining.as_secs_f32() * 4.) as u32 % 2 == 0;
+        set_color(position, blink);
+    }
 }
 
 fn hide_order(
@@ -189,104 +312,201 @@ fn update_arrow(
     visibility.is_visible = current_burger.ingredients.len() < order.ingredients.len();
 }
 
+/// Time left before the next ingredient is written on the menu.
+///
+/// It is restarted with [`Difficulty::menu_interval`] every time it finishes.
 #[derive(Component)]
-struct AddIngredientTimer(pub Timer);
+pub struct AddIngredientTimer(pub Timer);
+
+impl AddIngredientTimer {
+    pub fn remaining(&self) -> Duration {
+        self.0.duration().saturating_sub(self.0.elapsed())
+    }
+
+    /// Speed of the chef and menu animations, which are shortened when the menu changes faster than every 4 seconds
+    pub fn animation_speed(&self) -> f32 {
+        (self.0.duration().as_secs_f32() / 4.).min(1.)
+    }
+
+    /// Time the chef needs to reach the menu, in ms
+    pub fn chef_walk_time(&self) -> u64 {
+        (TWEEN_TIME as f32 * 2. * self.animation_speed()) as u64
+    }
+}
 
 pub struct AddIngredientEvent {
     pub ingredient: Ingredient,
+    /// Menu item replaced by the ingredient, chosen by [`write_on_menu`] when `None`
+    pub replaced: Option<usize>,
     pub timer: bool,
 }
 
+/// A new ingredient on the menu
+#[derive(Clone, Copy, Debug)]
+pub struct MenuChange {
+    pub ingredient: Ingredient,
+    /// Menu item replaced by the ingredient, `None` when it is added at the end of the menu
+    pub replaced: Option<usize>,
+}
+
+/// Next change of the menu, chosen in advance so that players can be warned
+#[derive(Default)]
+pub struct NextMenuChange(pub Option<MenuChange>);
+
 pub static MENU_SIZE: usize = 8;
+/// A sound is played this long before the menu changes
+const MENU_WARNING: Duration = Duration::from_secs(1);
+/// The menu item about to be replaced blinks this long before the menu changes
+const MENU_HIGHLIGHT: Duration = Duration::from_secs(3);
+
+fn reset_add_ingredient_timer(
+    mode: Res<GameMode>,
+    mut timer: ResMut<AddIngredientTimer>,
+    mut next_change: ResMut<NextMenuChange>,
+) {
+    timer.0 = Timer::new(mode.difficulty().menu_interval(0), false);
+    next_change.0 = None;
+}
 
 fn add_ingredient_watcher(
-    time: Res<Time>,
-    menu: Res<Menu>,
-    menu_on_display: Res<MenuOnDisplay>,
+    clock: Res<GameClock>,
+    mode: Res<GameMode>,
+    order_nb: Res<OrderNumber>,
     mut timer: ResMut<AddIngredientTimer>,
+    mut next_change: ResMut<NextMenuChange>,
     mut ev_add_ingredient: EventWriter<AddIngredientEvent>,
+    mut ev_sfx: EventWriter<PlaySfxEvent>,
 ) {
-    timer.0.tick(time.delta());
+    let remaining = timer.remaining();
+    timer.0.tick(clock.delta());
+
+    if remaining > MENU_WARNING && timer.remaining() <= MENU_WARNING {
+        ev_sfx.send(PlaySfxEvent(SFX::MenuWarning));
+    }
 
     if timer.0.finished() {
-        let ingredients = menu.ingredients();
-        let mut ingredients_not_in_menu: Vec<&Ingredient> = ingredients
-            .iter().filter(|&i| !menu_on_display.ingredients.contains(i))
-            .collect();
-        ingredients_not_in_menu.shuffle(&mut thread_rng());
-        ev_add_ingredient.send(AddIngredientEvent {
-            ingredient: **ingredients_not_in_menu.first().unwrap(),
-            timer: true
-        });
+        // The menu changes faster as burgers are served
+        timer.0 = Timer::new(mode.difficulty().menu_interval(order_nb.amount), false);
+        if let Some(MenuChange { ingredient, replaced }) = next_change.0.take() {
+            ev_add_ingredient.send(AddIngredientEvent { ingredient, replaced, timer: true });
+        }
     }
 }
 
 fn add_ingredient_to_menu(
+    menu_ref: Res<Menu>,
+    mode: Res<GameMode>,
+    mut rng: ResMut<GameRng>,
     mut menu: ResMut<MenuOnDisplay>,
+    mut next_change: ResMut<NextMenuChange>,
     mut ev_add_ingredient: EventReader<AddIngredientEvent>,
     mut ev_show_ingredient: EventWriter<ShowIngredientEvent>,
 ) {
-    for &AddIngredientEvent { ingredient, timer } in ev_add_ingredient.iter() {
+    for &AddIngredientEvent { ingredient, replaced, timer } in ev_add_ingredient.iter() {
         menu.ingredients_seen.insert(ingredient);
-        if menu.ingredients.len() <= MENU_SIZE {
-            // Add a new item at the end of the menu
-            menu.ingredients.push(ingredient);
-            ev_show_ingredient.send(ShowIngredientEvent {
-                replace: false,
-                position: menu.ingredients.iter().position(|&i| i == ingredient).unwrap(),
-                ingredient,
-                timer
-            });
-        } else {
-            // Replace a menu item
-            let mut to_replace = thread_rng().gen_range(2..MENU_SIZE);
-            if !ingredient.is_meat() && menu.ingredients.iter().filter(|i| i.is_meat()).count() < 2 {
-                while menu.ingredients.get(to_replace).unwrap().is_meat() {
-                    to_replace = thread_rng().gen_range(2..MENU_SIZE);
-                }
-            }
-            menu.ingredients.remove(to_replace);
-            menu.ingredients.insert(to_replace, ingredient);
-            ev_show_ingredient.send(ShowIngredientEvent {
-                replace: true,
-                position: to_replace,
-                ingredient,
-                timer
-            })
+        let (position, replace) = match replaced {
+            Some(_) => (apply_menu_change(&mut menu.ingredients, MenuChange { ingredient, replaced }), true),
+            None => write_on_menu(&menu_ref, &mut menu.ingredients, ingredient, mode.difficulty().menu_size, &mut rng.menu),
+        };
+        ev_show_ingredient.send(ShowIngredientEvent {
+            replace,
+            position,
+            ingredient,
+            timer
+        });
+    }
+
+    // The next change is chosen as soon as the menu has been written
+    if next_change.0.is_none() && !menu.ingredients.is_empty() {
+        let menu_ref = mode.menu(&menu_ref);
+        next_change.0 = Some(plan_menu_change(&menu_ref, &menu.ingredients, mode.difficulty().menu_size, &mut rng.menu));
+    }
+}
+
+/// Picks the next ingredient written on the menu among those which are not displayed
+pub fn next_menu_ingredient<R: Rng>(menu: &Menu, on_display: &[Ingredient], rng: &mut R) -> Ingredient {
+    let mut ingredients_not_in_menu: Vec<&Ingredient> = menu.ingredients
+        .iter().filter(|&i| !on_display.contains(i))
+        .collect();
+    ingredients_not_in_menu.shuffle(rng);
+    **ingredients_not_in_menu.first().unwrap()
+}
+
+/// Chooses the next ingredient of the menu, and the item it replaces when the menu has `menu_size` items
+pub fn plan_menu_change<R: Rng>(menu: &Menu, on_display: &[Ingredient], menu_size: usize, rng: &mut R) -> MenuChange {
+    let ingredient = next_menu_ingredient(menu, on_display, rng);
+    MenuChange { ingredient, replaced: menu_slot(menu, on_display, ingredient, menu_size, rng) }
+}
+
+/// Item replaced by `ingredient`, or `None` when there is room left on the menu.
+/// The menu always keeps at least one meat.
+pub fn menu_slot<R: Rng>(menu: &Menu, on_display: &[Ingredient], ingredient: Ingredient, menu_size: usize, rng: &mut R) -> Option<usize> {
+    if on_display.len() < menu_size { return None; }
+
+    let mut to_replace = rng.gen_range(2..menu_size - 1);
+    if !menu.is_meat(&ingredient) && on_display.iter().filter(|i| menu.is_meat(i)).count() < 2 {
+        while menu.is_meat(on_display.get(to_replace).unwrap()) {
+            to_replace = rng.gen_range(2..menu_size - 1);
+        }
+    }
+    Some(to_replace)
+}
+
+/// Returns the position of the new ingredient
+pub fn apply_menu_change(on_display: &mut Vec<Ingredient>, change: MenuChange) -> usize {
+    match change.replaced {
+        Some(position) => {
+            on_display[position] = change.ingredient;
+            position
+        }
+        None => {
+            on_display.push(change.ingredient);
+            on_display.len() - 1
         }
     }
 }
 
+/// Writes `ingredient` at the end of the menu, or in place of another item when the menu has `menu_size` items.
+///
+/// Returns the position of the ingredient and whether an item was replaced.
+pub fn write_on_menu<R: Rng>(menu: &Menu, on_display: &mut Vec<Ingredient>, ingredient: Ingredient, menu_size: usize, rng: &mut R) -> (usize, bool) {
+    let replaced = menu_slot(menu, on_display, ingredient, menu_size, rng);
+    (apply_menu_change(on_display, MenuChange { ingredient, replaced }), replaced.is_some())
+}
+
 #[derive(Component)]
 struct CurrentMenuIngredient(u8);
 
 fn spawn_menu_item(
     ingredient: Ingredient,
     item_number: u8,
+    bindings: &KeyBindings,
     mut commands: &mut Commands,
     textures: &Res<TextureAssets>,
     fonts: &Res<FontAssets>,
-    timer: bool,
+    chef_walk: Option<u64>,
 ) {
+    // Items written by the chef appear when the chef reaches the menu
+    let delay = chef_walk.unwrap_or(0);
     let button_pos = Vec2::new(20., 145. - 16. * item_number as f32);
     let (button, button_text) = spawn_button(
         &mut commands,
         button_pos,
-        ingredient.key(),
+        bindings.key(Binding::Ingredient(ingredient)),
         &textures,
         &fonts,
         true
     );
 
     let text_appear_animator = |color| Animator::new(
-        Delay::new(Duration::from_millis(if timer { TWEEN_TIME * 2 } else { 0 })).then(
+        Delay::new(Duration::from_millis(delay)).then(
             tween_text_opacity(color, TWEEN_TIME * 3, true)
         ));
 
     commands
         .entity(button)
         .insert(Animator::new(
-            Delay::new(Duration::from_millis(if timer { TWEEN_TIME * 2 } else { 0 })).then(
+            Delay::new(Duration::from_millis(delay)).then(
                 Tween::new(
                     EaseFunction::CubicOut,
                     TweeningType::Once,
@@ -295,7 +515,7 @@ fn spawn_menu_item(
                         start: 0.,
                         end: 0.,
                     }
-                ).with_completed_event(if timer { EV_CHALK } else { EV_NOTHING }).then(
+                ).with_completed_event(if chef_walk.is_some() { EV_CHALK } else { EV_NOTHING }).then(
                     tween_opacity(TWEEN_TIME * 3, true).with_completed_event(EV_ALLOW_BUTTON_UPDATE)
                 )
             ))
@@ -339,13 +559,11 @@ fn chalk(
     }
 }
 
-fn replace_menu_item(
-    ingredient: Ingredient,
+/// Fades out the menu item about to be replaced
+fn fade_menu_item(
     item_number: u8,
-    mut commands: &mut Commands,
-    textures: &Res<TextureAssets>,
-    fonts: &Res<FontAssets>,
-    mut queries: &mut ParamSet<(
+    commands: &mut Commands,
+    queries: &mut ParamSet<(
         Query<(Entity, &CurrentMenuIngredient), With<Text>>,
         Query<(Entity, &Children, &CurrentMenuIngredient), With<Letter>>
     )>
@@ -381,11 +599,12 @@ fn replace_menu_item(
             }
         }
     }
-    spawn_menu_item(ingredient, item_number, &mut commands, textures, fonts, true);
 }
 
 fn show_menu(
     mut ev_show_ingredient: EventReader<ShowIngredientEvent>,
+    add_ingredient_timer: Res<AddIngredientTimer>,
+    bindings: Res<KeyBindings>,
     mut commands: Commands,
     textures: Res<TextureAssets>,
     fonts: Res<FontAssets>,
@@ -395,25 +614,19 @@ fn show_menu(
     )>,
 ) {
     for &ShowIngredientEvent { replace, position, ingredient, timer } in ev_show_ingredient.iter() {
+        let chef_walk = if timer { Some(add_ingredient_timer.chef_walk_time()) } else { None };
         if replace {
-            replace_menu_item(
-                ingredient,
-                position as u8,
-                &mut commands,
-                &textures,
-                &fonts,
-                &mut queries,
-            );
-        } else {
-            spawn_menu_item(
-                ingredient,
-                position as u8,
-                &mut commands,
-                &textures,
-                &fonts,
-                timer,
-            );
+            fade_menu_item(position as u8, &mut commands, &mut queries);
         }
+        spawn_menu_item(
+            ingredient,
+            position as u8,
+            &bindings,
+            &mut commands,
+            &textures,
+            &fonts,
+            chef_walk,
+        );
     }
 }
 
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::{GameState, Labels};
+
+pub struct RngPlugin;
+
+/// Seeds are kept short enough to be typed and displayed on the title screen
+pub const MAX_SEED: u64 = 1_000_000_000;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Seed>()
+            .insert_resource(GameRng::new(0))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Cooking)
+                    .before(Labels::LogicSender)
+                    .with_system(reset_rng),
+            );
+    }
+}
+
+/// Seed used for the next game
+#[derive(Clone)]
+pub struct Seed {
+    pub value: u64,
+    /// A locked seed is kept from a game to another, otherwise a new one is drawn on the title screen
+    pub locked: bool,
+}
+
+impl Default for Seed {
+    fn default() -> Self {
+        Seed { value: random_seed(), locked: false }
+    }
+}
+
+pub fn random_seed() -> u64 {
+    thread_rng().gen_range(0..MAX_SEED)
+}
+
+/// Source of all gameplay randomness.
+///
+/// Each kind of draw has its own stream, so that the order in which systems run can't change the game.
+pub struct GameRng {
+    pub menu: StdRng,
+    pub orders: StdRng,
+    pub customers: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        let mut seeder = StdRng::seed_from_u64(seed);
+        GameRng {
+            menu: StdRng::seed_from_u64(seeder.gen()),
+            orders: StdRng::seed_from_u64(seeder.gen()),
+            customers: StdRng::seed_from_u64(seeder.gen()),
+        }
+    }
+}
+
+fn reset_rng(seed: Res<Seed>, mut rng: ResMut<GameRng>) {
+    *rng = GameRng::new(seed.value);
+}
@@ -1,24 +1,49 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
-use crate::GameState;
+use crate::{GameState, is_headless, Labels, spawn_text};
+use crate::clock::GameClock;
+use crate::difficulty::{Difficulty, GameMode};
 use crate::loading::{FontAssets, TextureAssets};
+use crate::stats::RunStats;
 
 pub struct ScorePlugin;
 
 impl Plugin for ScorePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Score>()
-            .add_system_set(SystemSet::on_enter(GameState::Cooking).with_system(init_score))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Cooking)
+                    .before(Labels::LogicSender)
+                    .with_system(reset_score)
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Cooking)
+                    .label(Labels::LogicReceiver)
+                    .after(Labels::LogicSender)
+                    .with_system(watch_time_limit)
+            );
+
+        if is_headless(app) { return; }
+
+        app.add_system_set(
+                SystemSet::on_enter(GameState::Cooking)
+                    .label(Labels::UI)
+                    .with_system(init_score)
+            )
             .add_system_set(SystemSet::on_update(GameState::Cooking).with_system(update_score))
             .add_system_set(SystemSet::on_exit(GameState::Cooking).with_system(clean_score));
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Score {
     pub score: i64,
     pub streak: i64,
     pub lives: i32,
+    /// Time added to the time limit by chains
+    pub bonus_time: Duration,
 }
 
 impl Default for Score {
@@ -27,13 +52,14 @@ impl Default for Score {
             score: 0,
             streak: 0,
             lives: 5,
+            bonus_time: Duration::ZERO,
         }
     }
 }
 
-pub const TIME_PER_INGREDIENT: f64 = 3.;
-pub const EXTRA_TIME_PER_BURGER: f64 = 3.;
 const SCORING_SLOPE_POS: f64 = 1.;
+/// Length of a chain which earns [`GameMode::chain_bonus`] seconds
+pub const CHAIN_BONUS_STREAK: i64 = 5;
 
 impl Score {
     pub fn compute_on_failure(&mut self) {
@@ -41,14 +67,35 @@ impl Score {
         self.lives -= 1;
     }
 
-    pub fn compute_on_success(&mut self, time: f64, difficulty: usize) {
+    pub fn compute_on_success(&mut self, time: f64, size: usize, difficulty: &Difficulty) {
         self.streak += 1;
-        let time_performance =
-            EXTRA_TIME_PER_BURGER + difficulty as f64 * TIME_PER_INGREDIENT - time;
+        let time_performance = difficulty.par_time(size) - time;
         let score = SCORING_SLOPE_POS * self.streak as f64 * time_performance;
         self.score += score.round() as i64;
     }
 
+    /// Every [`CHAIN_BONUS_STREAK`] burgers of a chain add `seconds` to the time limit
+    pub fn add_chain_bonus(&mut self, seconds: f64) {
+        if self.streak > 0 && self.streak % CHAIN_BONUS_STREAK == 0 {
+            self.bonus_time += Duration::from_secs_f64(seconds);
+        }
+    }
+
+    /// A burger with a few mistakes earns points in proportion to its accuracy, without the streak multiplier.
+    /// It breaks the streak, but doesn't cost a life.
+    pub fn compute_on_partial(&mut self, time: f64, size: usize, accuracy: f64, difficulty: &Difficulty) {
+        self.streak = 0;
+        let time_performance = difficulty.par_time(size) - time;
+        let score = SCORING_SLOPE_POS * time_performance * accuracy;
+        self.score += score.round() as i64;
+    }
+
+    /// Time before the end of a game with a time limit
+    pub fn time_left(&self, mode: &GameMode, elapsed: Duration) -> Option<Duration> {
+        let limit = Duration::from_secs_f64(mode.time_limit()?) + self.bonus_time;
+        Some(limit.saturating_sub(elapsed))
+    }
+
     fn to_display_text(&self) -> String {
         format!("{}", self.score)
     }
@@ -58,18 +105,62 @@ impl Score {
 struct ScoreUI;
 
 #[derive(Component)]
-pub struct LifeIcon(pub i32);
+struct LifeIcon(i32);
+
+/// Line shown next to the score or instead of it
+#[derive(Component)]
+enum Readout {
+    /// Typing speed in Zen mode
+    IngredientsPerMinute,
+    BurgersPerMinute,
+    /// Global clock of a game with a time limit
+    TimeLeft,
+}
+
+impl Readout {
+    fn to_display_text(&self, stats: &RunStats, time_left: Option<Duration>) -> String {
+        match self {
+            Readout::IngredientsPerMinute => format!("{:.0} IPM", stats.ingredients_per_minute().unwrap_or(0.)),
+            Readout::BurgersPerMinute => format!("{:.1} BPM", stats.burgers_per_minute().unwrap_or(0.)),
+            Readout::TimeLeft => {
+                let seconds = time_left.unwrap_or_default().as_secs_f64().ceil() as u64;
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            }
+        }
+    }
+}
+
+fn reset_score(mode: Res<GameMode>, mut score: ResMut<Score>) {
+    *score = Score { lives: mode.difficulty().lives, ..Score::default() };
+}
+
+/// Ends a game with a time limit once it is over
+fn watch_time_limit(
+    clock: Res<GameClock>,
+    score: Res<Score>,
+    mode: Res<GameMode>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if score.time_left(&mode, clock.elapsed()) == Some(Duration::ZERO) {
+        state.set(GameState::GameOver).unwrap_or_default();
+    }
+}
 
 fn init_score(
-    mut score: ResMut<Score>,
+    score: Res<Score>,
+    mode: Res<GameMode>,
+    stats: Res<RunStats>,
     mut commands: Commands,
     textures: Res<TextureAssets>,
     fonts: Res<FontAssets>,
 ) {
-    let default_score = Score::default();
-    score.score = default_score.score;
-    score.streak = default_score.streak;
-    score.lives = default_score.lives;
+    if mode.is_zen() {
+        for (readout, y) in [(Readout::IngredientsPerMinute, 156.), (Readout::BurgersPerMinute, 144.)] {
+            let text = readout.to_display_text(&stats, None);
+            spawn_readout(&mut commands, &fonts, readout, text, y);
+        }
+        return;
+    }
 
     commands
         .spawn_bundle(Text2dBundle {
@@ -90,12 +181,22 @@ fn init_score(
         })
         .insert(ScoreUI);
 
+    if !mode.has_lives() {
+        if let Some(time_left) = score.time_left(&mode, Duration::ZERO) {
+            let text = Readout::TimeLeft.to_display_text(&stats, Some(time_left));
+            spawn_readout(&mut commands, &fonts, Readout::TimeLeft, text, 144.);
+        }
+        return;
+    }
+
+    // Icons get closer when there are more than 6 lives
+    let spacing = (54. / score.lives as f32).min(9.);
     for i in 0..score.lives {
         commands
             .spawn_bundle(SpriteSheetBundle {
                 sprite: Default::default(),
                 texture_atlas: textures.life.clone(),
-                transform: Transform::from_xyz(320. - 54. + 9. * i as f32, 156. - 12., 5.),
+                transform: Transform::from_xyz(320. - 54. + spacing * i as f32, 156. - 12., 5.),
                 ..Default::default()
             })
             .insert(LifeIcon(i))
@@ -103,10 +204,32 @@ fn init_score(
     }
 }
 
-fn update_score(score: Res<Score>, mut query: Query<&mut Text, With<ScoreUI>>) {
+fn spawn_readout(commands: &mut Commands, fonts: &FontAssets, readout: Readout, text: String, y: f32) {
+    spawn_text(commands, fonts, text, Vec2::ZERO, Color::BLACK)
+        .insert(Transform::from_xyz(320. - 56., y, 5.))
+        .insert(readout)
+        .insert(ScoreUI);
+}
+
+fn update_score(
+    score: Res<Score>,
+    stats: Res<RunStats>,
+    clock: Res<GameClock>,
+    mode: Res<GameMode>,
+    mut query: Query<&mut Text, (With<ScoreUI>, Without<Readout>)>,
+    mut readouts: Query<(&mut Text, &Readout)>,
+    mut life_icons: Query<(&LifeIcon, &mut TextureAtlasSprite)>,
+) {
     for mut text in &mut query {
         text.sections[0].value = score.to_display_text();
     }
+    let time_left = score.time_left(&mode, clock.elapsed());
+    for (mut text, readout) in readouts.iter_mut() {
+        text.sections[0].value = readout.to_display_text(&stats, time_left);
+    }
+    for (LifeIcon(i), mut sprite) in life_icons.iter_mut() {
+        sprite.index = if *i >= score.lives { 1 } else { 0 };
+    }
 }
 
 fn clean_score(mut commands: Commands, spawned_ui_components: Query<Entity, With<ScoreUI>>) {
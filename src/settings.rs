@@ -0,0 +1,246 @@
+use bevy::prelude::*;
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+use crate::{GameState, is_headless, spawn_sprite, spawn_text};
+use crate::button::spawn_button;
+use crate::input::KeyboardReleaseEvent;
+use crate::loading::{FontAssets, TextureAssets};
+
+pub struct SettingsPlugin;
+
+/// [`PkvStore`] key of the [`Settings`]
+pub const SETTINGS: &str = "settings";
+
+#[derive(Component)]
+struct SettingsUi;
+
+/// Text of the option bound to these keys
+#[derive(Component)]
+struct SettingText(Vec<char>);
+
+/// Steps of the volume sliders, in percent
+const VOLUME_STEP: u8 = 10;
+
+/// How the keys pressed by the player are read
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum InputMode {
+    /// Place of the keys on the keyboard, named as on a QWERTY keyboard
+    #[default]
+    KeyCodes,
+    /// Characters typed with the keyboard layout of the system (the keys without one are still read from their place)
+    Characters,
+}
+
+impl InputMode {
+    fn name(&self) -> &'static str {
+        match self {
+            InputMode::KeyCodes => "KEY PLACES",
+            InputMode::Characters => "TYPED LETTERS",
+        }
+    }
+}
+
+/// Preferences of the player, changed on the options page of the title screen
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Settings {
+    /// Pause the game when the window loses focus
+    #[serde(default = "enabled")]
+    pub auto_pause: bool,
+    /// Volume of the music, in percent
+    #[serde(default = "default_bgm_volume")]
+    pub bgm_volume: u8,
+    #[serde(default)]
+    pub bgm_muted: bool,
+    /// Volume of the sound effects, in percent
+    #[serde(default = "default_sfx_volume")]
+    pub sfx_volume: u8,
+    #[serde(default)]
+    pub sfx_muted: bool,
+    #[serde(default)]
+    pub input_mode: InputMode,
+}
+
+fn enabled() -> bool { true }
+
+fn default_bgm_volume() -> u8 { 60 }
+
+fn default_sfx_volume() -> u8 { 30 }
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            auto_pause: true,
+            bgm_volume: default_bgm_volume(),
+            bgm_muted: false,
+            sfx_volume: default_sfx_volume(),
+            sfx_muted: false,
+            input_mode: InputMode::KeyCodes,
+        }
+    }
+}
+
+impl Settings {
+    /// Volume of the music channel, from 0 to 1
+    pub fn bgm_volume(&self) -> f64 {
+        if self.bgm_muted { 0. } else { self.bgm_volume as f64 / 100. }
+    }
+
+    /// Volume of the sound effects channel, from 0 to 1
+    pub fn sfx_volume(&self) -> f64 {
+        if self.sfx_muted { 0. } else { self.sfx_volume as f64 / 100. }
+    }
+
+    /// Mutes both channels, or unmutes them if they are both muted
+    pub fn toggle_mute(&mut self) {
+        let mute = !(self.bgm_muted && self.sfx_muted);
+        self.bgm_muted = mute;
+        self.sfx_muted = mute;
+    }
+
+    /// Keys and text of each line of the options page
+    fn options(&self) -> Vec<(Vec<char>, String)> {
+        vec![
+            (vec!['m'], format!("music: {}", on_off(!self.bgm_muted))),
+            (vec!['z', 'x'], format!("music volume {}", slider(self.bgm_volume))),
+            (vec!['n'], format!("sounds: {}", on_off(!self.sfx_muted))),
+            (vec!['c', 'v'], format!("sounds volume {}", slider(self.sfx_volume))),
+            (vec!['f'], format!("pause when unfocused: {}", on_off(self.auto_pause))),
+            (vec!['i'], format!("input: {}", self.input_mode.name())),
+        ]
+    }
+
+    /// Changes the option bound to `key`, returns `false` if there is none
+    fn toggle(&mut self, key: char) -> bool {
+        match key {
+            'm' => self.bgm_muted = !self.bgm_muted,
+            'z' => self.bgm_volume = self.bgm_volume.saturating_sub(VOLUME_STEP),
+            'x' => self.bgm_volume = (self.bgm_volume + VOLUME_STEP).min(100),
+            'n' => self.sfx_muted = !self.sfx_muted,
+            'c' => self.sfx_volume = self.sfx_volume.saturating_sub(VOLUME_STEP),
+            'v' => self.sfx_volume = (self.sfx_volume + VOLUME_STEP).min(100),
+            'f' => self.auto_pause = !self.auto_pause,
+            'i' => {
+                self.input_mode = match self.input_mode {
+                    InputMode::KeyCodes => InputMode::Characters,
+                    InputMode::Characters => InputMode::KeyCodes,
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "ON" } else { "OFF" }
+}
+
+/// Volume as a bar of ten steps
+fn slider(volume: u8) -> String {
+    let steps = (volume / VOLUME_STEP) as usize;
+    format!("{}{} {}%", "=".repeat(steps), "-".repeat(10usize.saturating_sub(steps)), volume)
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Settings>();
+
+        if is_headless(app) { return; }
+
+        app.add_startup_system(load_settings)
+            .add_system_set(SystemSet::on_enter(GameState::Settings).with_system(setup_settings))
+            .add_system_set(SystemSet::on_update(GameState::Settings).with_system(handle_input))
+            .add_system_set(SystemSet::on_exit(GameState::Settings).with_system(cleanup_settings));
+    }
+}
+
+fn load_settings(mut settings: ResMut<Settings>, pkv: Res<PkvStore>) {
+    if let Ok(mut saved) = pkv.get::<Settings>(SETTINGS) {
+        // The volumes are percents, a saved value may have been edited by hand
+        saved.bgm_volume = saved.bgm_volume.min(100);
+        saved.sfx_volume = saved.sfx_volume.min(100);
+        *settings = saved;
+    }
+}
+
+fn setup_settings(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    textures: Res<TextureAssets>,
+    fonts: Res<FontAssets>,
+) {
+    spawn_sprite(&mut commands, textures.background.clone(), Vec3::ZERO).insert(SettingsUi);
+    spawn_sprite(&mut commands, textures.counter.clone(), Vec3::new(0., 0., 0.5)).insert(SettingsUi);
+    spawn_text(&mut commands, &fonts, "OPTIONS".to_string(), Vec2::new(16., 160.), Color::WHITE).insert(SettingsUi);
+
+    for (i, (keys, text)) in settings.options().into_iter().enumerate() {
+        let y = 140. - 18. * i as f32;
+        for (j, key) in keys.iter().enumerate() {
+            let (button, _) = spawn_button(&mut commands, Vec2::new(16. + 14. * j as f32, y), *key, &textures, &fonts, false);
+            commands.entity(button).insert(SettingsUi);
+        }
+        spawn_text(&mut commands, &fonts, text, Vec2::new(48., y + 2.), Color::WHITE)
+            .insert(SettingText(keys))
+            .insert(SettingsUi);
+    }
+
+    let y = 140. - 18. * settings.options().len() as f32;
+    let (button, _) = spawn_button(&mut commands, Vec2::new(16., y), 'k', &textures, &fonts, false);
+    commands.entity(button).insert(SettingsUi);
+    spawn_text(&mut commands, &fonts, "key bindings".to_string(), Vec2::new(48., y + 2.), Color::WHITE).insert(SettingsUi);
+
+    let (button, _) = spawn_button(&mut commands, Vec2::new(124., 12.), ' ', &textures, &fonts, false);
+    commands.entity(button).insert(SettingsUi);
+    spawn_text(&mut commands, &fonts, "back".to_string(), Vec2::new(140., 14.), Color::WHITE).insert(SettingsUi);
+}
+
+fn handle_input(
+    mut events: EventReader<KeyboardReleaseEvent>,
+    mut state: ResMut<State<GameState>>,
+    mut settings: ResMut<Settings>,
+    mut pkv: ResMut<PkvStore>,
+    mut texts: Query<(&mut Text, &SettingText)>,
+) {
+    for KeyboardReleaseEvent(key) in events.iter() {
+        match *key {
+            ' ' | '<' => { state.set(GameState::TitleScreen).unwrap_or_default(); }
+            'k' => { state.set(GameState::Controls).unwrap_or_default(); }
+            key => {
+                if settings.toggle(key) {
+                    let _ = pkv.set(SETTINGS, &*settings);
+                }
+            }
+        }
+    }
+
+    if settings.is_changed() {
+        let options = settings.options();
+        for (mut text, SettingText(keys)) in texts.iter_mut() {
+            if let Some((_, value)) = options.iter().find(|(k, _)| k == keys) {
+                text.sections[0].value = value.clone();
+            }
+        }
+    }
+}
+
+fn cleanup_settings(
+    mut commands: Commands,
+    entities: Query<Entity, With<SettingsUi>>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slider_handles_volumes_above_100() {
+        assert_eq!(slider(30), "===------- 30%");
+        assert_eq!(slider(100), "========== 100%");
+        assert_eq!(slider(250), "========================= 250%");
+    }
+}
@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::{GameState, Labels};
+use crate::clock::GameClock;
+use crate::cooking::{CurrentBurger, ExpectingOrder, IngredientAddedEvent};
+use crate::ingredients::Ingredient;
+use crate::input::KeyboardEvent;
+use crate::order::{BurgerFinishedEvent, MenuOnDisplay, Order};
+
+pub struct StatsPlugin;
+
+/// Statistics of the current game, shown on the receipt of the game over screen
+#[derive(Clone, Default, Debug)]
+pub struct RunStats {
+    /// Burgers served, including the ones with partial credit
+    pub burgers: u32,
+    /// Wrong burgers
+    pub misses: u32,
+    /// Customers who left before their burger was served
+    pub out_of_time: u32,
+    pub chain: i64,
+    pub longest_chain: i64,
+    /// Time spent on served burgers, in seconds
+    pub build_time: f64,
+    /// Ingredients of the served burgers
+    pub ingredients_built: usize,
+    /// Ingredient keys typed while a customer was waiting
+    pub keystrokes: u32,
+    /// Ingredient keys matching the order
+    pub correct_keystrokes: u32,
+    /// Ingredients of the orders replaced by a wrong key
+    pub missed_ingredients: HashMap<Ingredient, u32>,
+    /// Ingredients put on burgers, right or wrong
+    pub placed_ingredients: HashMap<Ingredient, u32>,
+    /// Time spent cooking, in seconds
+    pub time_cooked: f64,
+}
+
+impl RunStats {
+    /// Average time to place an ingredient on a served burger, in seconds
+    pub fn time_per_ingredient(&self) -> Option<f64> {
+        if self.ingredients_built == 0 { return None; }
+        Some(self.build_time / self.ingredients_built as f64)
+    }
+
+    /// Proportion of ingredient keys which matched the order, from 0 to 1
+    pub fn keystroke_accuracy(&self) -> Option<f64> {
+        if self.keystrokes == 0 { return None; }
+        Some(self.correct_keystrokes as f64 / self.keystrokes as f64)
+    }
+
+    /// Ingredients placed per minute of cooking, right or wrong
+    pub fn ingredients_per_minute(&self) -> Option<f64> {
+        if self.time_cooked <= 0. { return None; }
+        Some(self.placed_ingredients.values().sum::<u32>() as f64 * 60. / self.time_cooked)
+    }
+
+    /// Burgers served per minute of cooking
+    pub fn burgers_per_minute(&self) -> Option<f64> {
+        if self.time_cooked <= 0. { return None; }
+        Some(self.burgers as f64 * 60. / self.time_cooked)
+    }
+
+    pub fn most_missed_ingredient(&self) -> Option<Ingredient> {
+        // Ties are broken by the order of the ingredients so that the receipt doesn't depend on the hash
+        self.missed_ingredients.iter()
+            .max_by_key(|(ingredient, count)| (**count, std::cmp::Reverse(**ingredient as usize)))
+            .map(|(ingredient, _)| *ingredient)
+    }
+}
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_system_set(SystemSet::on_enter(GameState::Cooking).with_system(reset_stats))
+            .add_system_set(SystemSet::on_update(GameState::Cooking)
+                .before(Labels::LogicSender)
+                .with_system(record_keystrokes)
+            )
+            .add_system_set(SystemSet::on_update(GameState::Cooking)
+                .label(Labels::LogicReceiver)
+                .before(Labels::UI)
+                .after(Labels::LogicSender)
+                .with_system(record_burgers)
+                .with_system(record_time)
+            )
+            .add_system_set(SystemSet::on_update(GameState::Cooking)
+                .after(Labels::LogicReceiver)
+                .with_system(record_placements)
+            );
+    }
+}
+
+fn reset_stats(mut stats: ResMut<RunStats>) {
+    *stats = RunStats::default();
+}
+
+/// Compares each ingredient key to the ingredient expected at its position in the order
+fn record_keystrokes(
+    order: Res<Order>,
+    expecting_order: Res<ExpectingOrder>,
+    current_burger: Res<CurrentBurger>,
+    menu: Res<MenuOnDisplay>,
+    mut stats: ResMut<RunStats>,
+    mut input: EventReader<KeyboardEvent>,
+) {
+    let mut position = current_burger.ingredients.len();
+    for KeyboardEvent(key) in input.iter() {
+        if !expecting_order.0 { continue; }
+        match *key {
+            '<' | ' ' => position = 0,
+            _ => {
+                let ingredient = match Ingredient::from_key(key) {
+                    Some(ingredient) if menu.ingredients_seen.contains(&ingredient) => ingredient,
+                    _ => continue,
+                };
+                stats.keystrokes += 1;
+                match order.ingredients.get(position) {
+                    Some(expected) if *expected == ingredient => stats.correct_keystrokes += 1,
+                    Some(expected) => *stats.missed_ingredients.entry(*expected).or_insert(0) += 1,
+                    None => {}
+                }
+                position += 1;
+            }
+        }
+    }
+}
+
+fn record_burgers(
+    clock: Res<GameClock>,
+    order: Res<Order>,
+    mut stats: ResMut<RunStats>,
+    mut ev_burger_finished: EventReader<BurgerFinishedEvent>,
+) {
+    for &BurgerFinishedEvent { correct, out_of_time, partial, .. } in ev_burger_finished.iter() {
+        if correct || partial {
+            stats.burgers += 1;
+            stats.build_time += (clock.elapsed() - order.creation_time).as_secs_f64();
+            stats.ingredients_built += order.ingredients.len();
+            // Like the streak of the score, a partial burger ends the chain
+            if correct {
+                stats.chain += 1;
+                stats.longest_chain = stats.longest_chain.max(stats.chain);
+            } else {
+                stats.chain = 0;
+            }
+        } else {
+            stats.chain = 0;
+            if out_of_time { stats.out_of_time += 1; } else { stats.misses += 1; }
+        }
+    }
+}
+
+fn record_placements(mut stats: ResMut<RunStats>, mut ev_ingredient_added: EventReader<IngredientAddedEvent>) {
+    for IngredientAddedEvent { ingredient, .. } in ev_ingredient_added.iter() {
+        *stats.placed_ingredients.entry(*ingredient).or_insert(0) += 1;
+    }
+}
+
+fn record_time(clock: Res<GameClock>, mut stats: ResMut<RunStats>) {
+    stats.time_cooked += clock.delta().as_secs_f64();
+}
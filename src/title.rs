@@ -1,17 +1,23 @@
 use std::time::Duration;
 
 use bevy::prelude::*;
-use bevy_kira_audio::{AudioChannel, AudioControl};
+use bevy_pkv::PkvStore;
 use bevy_tweening::{Animator, EaseFunction, Tween, TweeningType};
 use bevy_tweening::lens::TransformPositionLens;
 
 use crate::{GameState, spawn_sprite};
-use crate::audio::{BGM, BgmChannel, PlayBgmEvent, SfxChannel};
+use crate::audio::{BGM, PlayBgmEvent};
 use crate::button::spawn_button;
 use crate::cooking::MadnessMode;
+use crate::difficulty::{DIFFICULTY_SETTINGS, Difficulty, DifficultySettings, GameMode};
+use crate::ingredients::{Menu, MenuBook};
 use crate::input::{Actions, KeyboardReleaseEvent};
-use crate::loading::{FontAssets, TextureAssets};
-use crate::tween::{tween_position, tween_text_opacity, TWEEN_TIME};
+use crate::loading::{FontAssets, MENU_CHOICE, MenuAssets, TextureAssets};
+use crate::replay::{LAST_REPLAY, Playback, Replay};
+use crate::rng::{MAX_SEED, random_seed, Seed};
+use crate::settings::{SETTINGS, Settings};
+use crate::tutorial::Tutorial;
+use crate::tween::{tween_opacity, tween_position, tween_text_opacity, TWEEN_TIME};
 
 pub struct TitlePlugin;
 
@@ -21,6 +27,15 @@ struct TitleUi;
 #[derive(Component)]
 struct TitleBurgerIngredient(usize);
 
+#[derive(Component)]
+struct SeedText;
+
+#[derive(Component)]
+struct DifficultyText;
+
+#[derive(Component)]
+struct MenuText;
+
 struct TitleState {
     burger_open: bool,
 }
@@ -38,7 +53,14 @@ impl Plugin for TitlePlugin {
         app
             .init_resource::<TitleState>()
             .add_system_set(SystemSet::on_enter(GameState::TitleScreen).with_system(setup_title))
-            .add_system_set(SystemSet::on_update(GameState::TitleScreen).with_system(handle_input))
+            .add_system_set(
+                SystemSet::on_update(GameState::TitleScreen)
+                    .with_system(open_burger)
+                    .with_system(spawn_options.after(open_burger))
+                    .with_system(handle_input)
+                    .with_system(update_texts.after(handle_input))
+                    .with_system(change_menu)
+            )
             .add_system_set(SystemSet::on_exit(GameState::TitleScreen).with_system(cleanup_title));
     }
 }
@@ -49,9 +71,13 @@ fn setup_title(
     textures: Res<TextureAssets>,
     fonts: Res<FontAssets>,
     mut state: ResMut<TitleState>,
+    mut seed: ResMut<Seed>,
 ) {
     bgm.send(PlayBgmEvent(BGM::Title));
     state.burger_open = false;
+    if !seed.locked {
+        seed.value = random_seed();
+    }
 
     spawn_sprite(&mut commands, textures.background.clone(), Vec3::ZERO.clone()).insert(TitleUi);
     spawn_sprite(&mut commands, textures.counter.clone(), Vec3::new(0., 0., 0.5,)).insert(TitleUi);
@@ -86,6 +112,25 @@ fn setup_title(
         })
         .insert(TitleUi);
 
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text {
+                sections: vec![TextSection {
+                    value: seed_text(&seed),
+                    style: TextStyle {
+                        font: fonts.axg.clone(),
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                }],
+                alignment: TextAlignment::TOP_LEFT,
+            },
+            transform: Transform::from_xyz(4., 178., 1.),
+            ..Default::default()
+        })
+        .insert(SeedText)
+        .insert(TitleUi);
+
     let (button_entity, _) = spawn_button(&mut commands, Vec2::new(124., 12.), ' ', &textures, &fonts, false);
     commands
         .entity(button_entity)
@@ -108,100 +153,292 @@ fn setup_title(
     }
 }
 
-fn handle_input(
+/// [space] opens the burger and shows the options
+fn open_burger(
     mut commands: Commands,
     mut title_state: ResMut<TitleState>,
-    mut state: ResMut<State<GameState>>,
     input: Res<Actions>,
-    mut events: EventReader<KeyboardReleaseEvent>,
+    ingredients: Query<(Entity, &Transform, &TitleBurgerIngredient)>,
+) {
+    if title_state.burger_open || !input.pressed.contains(&' ') { return; }
+    title_state.burger_open = true;
+
+    // Spread burger
+    for (entity, transform, TitleBurgerIngredient(n)) in ingredients.iter() {
+        let initial_pos = transform.translation.clone();
+        let end_pos = Vec3::new(initial_pos.x, initial_pos.y + *n as f32 * 12., initial_pos.z);
+        commands
+            .entity(entity)
+            .insert(Animator::new(Tween::new(
+                EaseFunction::CubicOut,
+                TweeningType::Once,
+                Duration::from_secs_f32(1.5),
+                TransformPositionLens {
+                    start: initial_pos,
+                    end: end_pos,
+                },
+            )));
+    }
+}
+
+fn spawn_options(
+    mut commands: Commands,
+    title_state: Res<TitleState>,
     textures: Res<TextureAssets>,
     fonts: Res<FontAssets>,
-    bgm: Res<AudioChannel<BgmChannel>>,
-    sfx: Res<AudioChannel<SfxChannel>>,
-    ingredients: Query<(Entity, &Transform, &TitleBurgerIngredient)>,
+    difficulty_settings: Res<DifficultySettings>,
+    menu: Res<Menu>,
 ) {
-    if !title_state.burger_open && input.pressed.contains(&' ') {
-        title_state.burger_open = true;
-
-        // Spread burger
-        for (entity, transform, TitleBurgerIngredient(n)) in ingredients.iter() {
-            let initial_pos = transform.translation.clone();
-            let end_pos = Vec3::new(initial_pos.x, initial_pos.y + *n as f32 * 12., initial_pos.z);
-            commands
-                .entity(entity)
-                .insert(Animator::new(Tween::new(
-                    EaseFunction::CubicOut,
-                    TweeningType::Once,
-                    Duration::from_secs_f32(1.5),
-                    TransformPositionLens {
-                        start: initial_pos,
-                        end: end_pos,
-                    },
-                )));
-        }
+    if !title_state.is_changed() || !title_state.burger_open { return; }
 
-        // Spawn options & buttons
-        let options = vec![
-            ('c', "classic", 86.),
-            ('d', "madness", 66.),
-            ('u', "music", 46.),
-        ];
-
-        for (letter, name, y_pos) in options {
-            let y_start = 40.;
-            let (button, _) = spawn_button(&mut commands, Vec2::new(160., y_start), letter, &textures, &fonts, false);
-            commands
-                .entity(button)
-                .insert(TitleUi)
-                .insert(Animator::new(
-                    tween_position(Vec2::new(160., y_start), Vec2::new(118., y_pos), 2., TWEEN_TIME * 3)
-                    ));
-            commands
-                .spawn_bundle(Text2dBundle {
-                    text: Text {
-                        sections: vec![TextSection {
-                            value: name.to_string(),
-                            style: TextStyle {
-                                font: fonts.axg.clone(),
-                                font_size: 16.0,
-                                color: Color::rgba(1., 1., 1., 0.),
-                            },
-                        }],
-                        alignment: TextAlignment::CENTER,
-                        ..Default::default()
-                    },
-                    transform: Transform::from_xyz(160., (46. + y_pos) / 2. - 7., 1.),
-                    ..Default::default()
-                })
-                .insert(TitleUi)
-                .insert(Animator::new(
-                    tween_text_opacity(Color::WHITE, TWEEN_TIME * 3, true)
-                ))
-                .insert(Animator::new(
-                    tween_position(Vec2::new(160., (46. + y_pos) / 2. - 7.), Vec2::new(160., y_pos + 5.), 2., TWEEN_TIME * 3)
+    // Spawn options & buttons
+    let options = vec![
+        ('c', "classic", 96.),
+        ('d', "madness", 78.),
+        ('t', "time attack", 60.),
+        ('z', "zen", 42.),
+        ('u', "music", 24.),
+    ];
+
+    for (letter, name, y_pos) in options {
+        let y_start = 40.;
+        let (button, _) = spawn_button(&mut commands, Vec2::new(160., y_start), letter, &textures, &fonts, false);
+        commands
+            .entity(button)
+            .insert(TitleUi)
+            .insert(Animator::new(
+                tween_position(Vec2::new(160., y_start), Vec2::new(118., y_pos), 2., TWEEN_TIME * 3)
                 ));
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value: name.to_string(),
+                        style: TextStyle {
+                            font: fonts.axg.clone(),
+                            font_size: 16.0,
+                            color: Color::rgba(1., 1., 1., 0.),
+                        },
+                    }],
+                    alignment: TextAlignment::CENTER,
+                    ..Default::default()
+                },
+                transform: Transform::from_xyz(160., (46. + y_pos) / 2. - 7., 1.),
+                ..Default::default()
+            })
+            .insert(TitleUi)
+            .insert(Animator::new(
+                tween_text_opacity(Color::WHITE, TWEEN_TIME * 3, true)
+            ))
+            .insert(Animator::new(
+                tween_position(Vec2::new(160., (46. + y_pos) / 2. - 7.), Vec2::new(160., y_pos + 5.), 2., TWEEN_TIME * 3)
+            ));
+    }
+
+    // Secondary options are listed on the right side of the screen
+    let side_options = vec![
+        ('h', "tutorial"),
+        ('g', "campaign"),
+        ('y', "daily"),
+        ('p', "replay"),
+        ('m', menu.name.as_str()),
+        ('l', difficulty_settings.preset.name()),
+        ('s', "stats"),
+        ('a', "awards"),
+        ('o', "options"),
+    ];
+
+    for (i, (letter, name)) in side_options.into_iter().enumerate() {
+        let label = spawn_side_option(&mut commands, letter, name, Vec2::new(236., 136. - 16. * i as f32), &textures, &fonts);
+        match letter {
+            'l' => { commands.entity(label).insert(DifficultyText); }
+            'm' => { commands.entity(label).insert(MenuText); }
+            _ => {}
         }
     }
+}
 
+fn handle_input(
+    mut commands: Commands,
+    mut state: ResMut<State<GameState>>,
+    mut events: EventReader<KeyboardReleaseEvent>,
+    mut settings: ResMut<Settings>,
+    mut seed: ResMut<Seed>,
+    mut difficulty_settings: ResMut<DifficultySettings>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    // Several options can be released in the same frame, only the first one leaving the title is followed
+    let mut next = None;
     for KeyboardReleaseEvent(char) in events.iter() {
+        if next.is_some() { continue; }
         match *char {
             'c' => {
                 commands.insert_resource(MadnessMode(false));
-                state.set(GameState::Cooking).unwrap();
+                commands.insert_resource(GameMode::Classic(difficulty_settings.difficulty()));
+                next = Some(GameState::Cooking);
             }
             'd' => {
                 commands.insert_resource(MadnessMode(true));
-                state.set(GameState::Cooking).unwrap();
+                commands.insert_resource(GameMode::Classic(Difficulty::madness()));
+                next = Some(GameState::Cooking);
+            }
+            't' => {
+                commands.insert_resource(MadnessMode(false));
+                commands.insert_resource(GameMode::time_attack());
+                next = Some(GameState::Cooking);
+            }
+            'z' => {
+                commands.insert_resource(MadnessMode(false));
+                commands.insert_resource(GameMode::zen());
+                next = Some(GameState::Cooking);
+            }
+            'h' => {
+                commands.insert_resource(Tutorial::default());
+                commands.insert_resource(MadnessMode(false));
+                commands.insert_resource(GameMode::tutorial());
+                next = Some(GameState::Cooking);
+            }
+            'l' => {
+                difficulty_settings.preset = difficulty_settings.preset.next();
+                let _ = pkv.set(DIFFICULTY_SETTINGS, &*difficulty_settings);
+            }
+            's' => {
+                next = Some(GameState::Profile);
+            }
+            'y' => {
+                next = Some(GameState::Daily);
+            }
+            'g' => {
+                next = Some(GameState::Campaign);
+            }
+            'a' => {
+                next = Some(GameState::Achievements);
+            }
+            'o' => {
+                next = Some(GameState::Settings);
             }
             'u' => {
-                match bgm.is_playing_sound() {
-                    true => { bgm.pause(); sfx.pause(); }
-                    false => { bgm.resume(); sfx.resume(); }
+                settings.toggle_mute();
+                let _ = pkv.set(SETTINGS, &*settings);
+            }
+            'p' => {
+                if let Ok(replay) = pkv.get::<Replay>(LAST_REPLAY) {
+                    commands.insert_resource(Playback::new(replay));
+                }
+            }
+            'r' => {
+                *seed = Seed { value: random_seed(), locked: false };
+            }
+            '<' => {
+                seed.value /= 10;
+                seed.locked = true;
+            }
+            '0'..='9' => {
+                let digit = char.to_digit(10).unwrap() as u64;
+                if !seed.locked {
+                    *seed = Seed { value: digit, locked: true };
+                } else if seed.value * 10 + digit < MAX_SEED {
+                    seed.value = seed.value * 10 + digit;
                 }
             }
             _ => {}
         }
     }
+    if let Some(next) = next {
+        state.set(next).unwrap_or_default();
+    }
+}
+
+fn update_texts(
+    seed: Res<Seed>,
+    mut seed_texts: Query<&mut Text, (With<SeedText>, Without<DifficultyText>)>,
+    difficulty_settings: Res<DifficultySettings>,
+    mut difficulty_texts: Query<&mut Text, (With<DifficultyText>, Without<SeedText>)>,
+) {
+    if seed.is_changed() {
+        for mut text in seed_texts.iter_mut() {
+            text.sections[0].value = seed_text(&seed);
+        }
+    }
+
+    if difficulty_settings.is_changed() {
+        for mut text in difficulty_texts.iter_mut() {
+            text.sections[0].value = difficulty_settings.preset.name().to_string();
+        }
+    }
+}
+
+/// [m] serves the next menu of the book
+fn change_menu(
+    mut events: EventReader<KeyboardReleaseEvent>,
+    mut menu: ResMut<Menu>,
+    menus: Res<MenuAssets>,
+    books: Res<Assets<MenuBook>>,
+    mut pkv: ResMut<PkvStore>,
+    mut texts: Query<&mut Text, With<MenuText>>,
+) {
+    let book = match books.get(&menus.book) {
+        Some(book) => book,
+        None => return,
+    };
+
+    for KeyboardReleaseEvent(key) in events.iter() {
+        if *key == 'm' {
+            *menu = book.next(&menu.name).clone();
+            let _ = pkv.set(MENU_CHOICE, &menu.name);
+        }
+    }
+
+    if menu.is_changed() {
+        for mut text in texts.iter_mut() {
+            text.sections[0].value = menu.name.clone();
+        }
+    }
+}
+
+fn spawn_side_option(
+    commands: &mut Commands,
+    letter: char,
+    name: &str,
+    position: Vec2,
+    textures: &Res<TextureAssets>,
+    fonts: &Res<FontAssets>,
+) -> Entity {
+    let (button, button_text) = spawn_button(commands, position, letter, textures, fonts, true);
+    commands
+        .entity(button)
+        .insert(TitleUi)
+        .insert(Animator::new(tween_opacity(TWEEN_TIME * 3, true)));
+    commands
+        .entity(button_text)
+        .insert(Animator::new(tween_text_opacity(Color::WHITE, TWEEN_TIME * 3, true)));
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text {
+                sections: vec![TextSection {
+                    value: name.to_string(),
+                    style: TextStyle {
+                        font: fonts.axg.clone(),
+                        font_size: 16.0,
+                        color: Color::rgba(1., 1., 1., 0.),
+                    },
+                }],
+                ..Default::default()
+            },
+            transform: Transform::from_xyz(position.x + 16., position.y + 13., 1.),
+            ..Default::default()
+        })
+        .insert(TitleUi)
+        .insert(Animator::new(tween_text_opacity(Color::WHITE, TWEEN_TIME * 3, true)))
+        .id()
+}
+
+fn seed_text(seed: &Seed) -> String {
+    if seed.locked {
+        format!("seed {}", seed.value)
+    } else {
+        format!("seed {} (random)", seed.value)
+    }
 }
 
 fn cleanup_title(
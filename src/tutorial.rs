@@ -0,0 +1,227 @@
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::{GameState, is_headless, Labels, spawn_sprite, spawn_text};
+use crate::bindings::{Binding, KeyBindings};
+use crate::button::spawn_button;
+use crate::clock::GameClock;
+use crate::cooking::IngredientAddedEvent;
+use crate::ingredients::{Ingredient, Menu};
+use crate::input::KeyboardEvent;
+use crate::loading::{FontAssets, TextureAssets};
+use crate::order::BurgerFinishedEvent;
+use crate::restaurant::{AddIngredientEvent, Arrow};
+
+pub struct TutorialPlugin;
+
+/// The game goes back to the title screen this long after the last step
+const DONE_TIME: Duration = Duration::from_secs(6);
+/// Height of the guide, above the burger and the customer
+const GUIDE_Z: f32 = 12.;
+
+/// Steps of the tutorial, each one ends when the player does what the guide asks
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum TutorialStep {
+    Bread,
+    Meat,
+    Send,
+    Clear,
+    /// Wait for the chef to write a new dish on the menu
+    Chef,
+    Done,
+}
+
+impl TutorialStep {
+    /// Lines of the speech bubble
+    fn lines(&self) -> [&'static str; 6] {
+        match self {
+            TutorialStep::Bread => ["Welcome to", "the kitchen!", "A burger", "starts with", "bread, press", "its key:"],
+            TutorialStep::Meat => ["Follow the", "arrow: now", "a meat! Its", "key is on", "the menu", "on the left"],
+            TutorialStep::Send => ["Stack the", "rest of the", "order, the", "top bread", "too. Then", "send it:"],
+            TutorialStep::Clear => ["A mistake?", "Put a few", "ingredients", "on the plate", "and throw", "them away:"],
+            TutorialStep::Chef => ["Keep cooking", "and watch:", "the chef", "writes new", "dishes on", "the menu"],
+            TutorialStep::Done => ["Well done!", "You are", "ready for", "a classic", "game, press", "[c] on title"],
+        }
+    }
+
+    /// Action whose key is shown under the lines
+    fn binding(&self) -> Option<Binding> {
+        match self {
+            TutorialStep::Bread => Some(Binding::Ingredient(Ingredient::Bread)),
+            TutorialStep::Send => Some(Binding::Send),
+            TutorialStep::Clear => Some(Binding::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// Inserted before cooking to play a tutorial game (see [`crate::difficulty::GameMode::Tutorial`])
+pub struct Tutorial {
+    pub step: TutorialStep,
+    /// Time of the game when the last step was reached
+    finished: Duration,
+}
+
+impl Default for Tutorial {
+    fn default() -> Self {
+        Tutorial { step: TutorialStep::Bread, finished: Duration::ZERO }
+    }
+}
+
+#[derive(Component)]
+struct TutorialUi;
+
+/// Content of the speech bubble, drawn again at each step
+#[derive(Component)]
+struct TutorialLine;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Cooking)
+                .after(Labels::LogicSender)
+                .after(Labels::LogicReceiver)
+                .before(Labels::UI)
+                .with_system(advance_tutorial),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Cooking).with_system(stop_tutorial));
+
+        if is_headless(app) { return; }
+
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Cooking)
+                .label(Labels::UI)
+                .with_system(spawn_guide)
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Cooking)
+                .label(Labels::UI)
+                .after(Labels::LogicReceiver)
+                .with_system(update_guide)
+                .with_system(highlight_arrow),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Cooking).with_system(clean_guide));
+    }
+}
+
+/// Events the steps of the tutorial wait for
+#[derive(SystemParam)]
+struct StepEvents<'w, 's> {
+    ingredient_added: EventReader<'w, 's, IngredientAddedEvent>,
+    burger_finished: EventReader<'w, 's, BurgerFinishedEvent>,
+    add_ingredient: EventReader<'w, 's, AddIngredientEvent>,
+    input: EventReader<'w, 's, KeyboardEvent>,
+}
+
+fn advance_tutorial(
+    tutorial: Option<ResMut<Tutorial>>,
+    menu: Res<Menu>,
+    clock: Res<GameClock>,
+    mut events: StepEvents,
+    mut state: ResMut<State<GameState>>,
+) {
+    // Every event is read, so that the ones sent before a step don't complete it
+    let added: Vec<Ingredient> = events.ingredient_added.iter().map(|event| event.ingredient).collect();
+    let sent = events.burger_finished.iter().count() > 0;
+    let cleared = events.input.iter().any(|KeyboardEvent(key)| *key == '<');
+    let written = events.add_ingredient.iter().any(|event| event.timer);
+
+    let mut tutorial = match tutorial {
+        Some(tutorial) => tutorial,
+        None => return,
+    };
+
+    let next = match tutorial.step {
+        TutorialStep::Bread if added.contains(&Ingredient::Bread) => TutorialStep::Meat,
+        TutorialStep::Meat if added.iter().any(|ingredient| menu.is_meat(ingredient)) => TutorialStep::Send,
+        TutorialStep::Send if sent => TutorialStep::Clear,
+        TutorialStep::Clear if cleared => TutorialStep::Chef,
+        TutorialStep::Chef if written => TutorialStep::Done,
+        TutorialStep::Done if clock.elapsed() >= tutorial.finished + DONE_TIME => {
+            state.set(GameState::TitleScreen).unwrap_or_default();
+            return;
+        }
+        _ => return,
+    };
+
+    tutorial.step = next;
+    if next == TutorialStep::Done {
+        tutorial.finished = clock.elapsed();
+    }
+}
+
+fn stop_tutorial(mut commands: Commands) {
+    commands.remove_resource::<Tutorial>();
+}
+
+/// The guide is a small chef peeking above the counter, talking in a speech bubble
+fn spawn_guide(
+    mut commands: Commands,
+    tutorial: Option<Res<Tutorial>>,
+    textures: Res<TextureAssets>,
+) {
+    if tutorial.is_none() { return; }
+
+    spawn_sprite(&mut commands, textures.chef.clone(), Vec3::new(128., 4., 0.4))
+        .insert(Transform::from_xyz(128., 4., 0.4).with_scale(Vec3::new(0.5, 0.5, 1.)))
+        .insert(TutorialUi);
+    spawn_sprite(&mut commands, textures.bubble.clone(), Vec3::new(104., 84., GUIDE_Z)).insert(TutorialUi);
+}
+
+fn update_guide(
+    mut commands: Commands,
+    tutorial: Option<Res<Tutorial>>,
+    bindings: Res<KeyBindings>,
+    lines: Query<Entity, With<TutorialLine>>,
+    textures: Res<TextureAssets>,
+    fonts: Res<FontAssets>,
+) {
+    let tutorial = match tutorial {
+        Some(tutorial) if tutorial.is_changed() => tutorial,
+        _ => return,
+    };
+
+    for entity in lines.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for (i, line) in tutorial.step.lines().iter().enumerate() {
+        spawn_text(&mut commands, &fonts, line.to_string(), Vec2::ZERO, Color::BLACK)
+            .insert(Transform::from_xyz(110., 166. - 10. * i as f32, GUIDE_Z + 1.))
+            .insert(TutorialLine)
+            .insert(TutorialUi);
+    }
+
+    if let Some(binding) = tutorial.step.binding() {
+        let (button, _) = spawn_button(&mut commands, Vec2::new(128., 96.), bindings.key(binding), &textures, &fonts, false);
+        commands
+            .entity(button)
+            .insert(Transform::from_xyz(128., 96., GUIDE_Z + 1.))
+            .insert(TutorialLine)
+            .insert(TutorialUi);
+    }
+}
+
+/// The arrow blinks while the guide talks about the order
+fn highlight_arrow(
+    tutorial: Option<Res<Tutorial>>,
+    clock: Res<GameClock>,
+    mut arrow: Query<&mut Sprite, With<Arrow>>,
+) {
+    let blink = tutorial.map_or(false, |tutorial| matches!(tutorial.step, TutorialStep::Meat | TutorialStep::Send))
+        && (clock.elapsed().as_millis() / 300) % 2 == 0;
+    for mut sprite in arrow.iter_mut() {
+        sprite.color = if blink { Color::rgb(1., 0.85, 0.3) } else { Color::WHITE };
+    }
+}
+
+fn clean_guide(
+    mut commands: Commands,
+    entities: Query<Entity, With<TutorialUi>>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
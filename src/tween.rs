@@ -11,8 +11,6 @@ use crate::{Labels, Transform};
 pub const TWEEN_TIME: u64 = 500;
 
 pub const EV_DELETE: u64 = 0;
-pub const EV_CUSTOMER_WAITING_TIME_ELAPSED: u64 = 1;
-pub const EV_CUSTOMER_EXITED: u64 = 2;
 pub const EV_ALLOW_BUTTON_UPDATE: u64 = 3;
 pub const EV_CHALK: u64 = 4;
 pub const EV_NOTHING: u64 = 999;
@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use miam::achievements::{Achievement, Achievements};
+use miam::score::Score;
+
+mod common;
+
+use common::start_game;
+
+#[test]
+fn ten_perfect_burgers_unlock_the_chain_achievement() {
+    let mut game = start_game(10);
+    for _ in 0..10 {
+        assert!(!game.resource::<Achievements>().is_unlocked(Achievement::Chain10));
+        let keys = game.order_keys();
+        game.type_keys(&keys);
+        game.press(' ');
+        game.advance(Duration::from_secs(1));
+    }
+
+    assert_eq!(game.resource::<Score>().streak, 10);
+    assert!(game.resource::<Achievements>().is_unlocked(Achievement::Chain10));
+}
@@ -0,0 +1,40 @@
+use miam::bindings::{Binding, KeyBindings, KeyboardLayout};
+use miam::ingredients::Ingredient;
+
+#[test]
+fn presets_keep_ingredients_in_place() {
+    let azerty = KeyBindings::preset(KeyboardLayout::Azerty);
+    assert_eq!(azerty.key(Binding::Ingredient(Ingredient::Bacon)), 'q');
+    assert_eq!(azerty.key(Binding::Ingredient(Ingredient::Steak)), 's');
+    assert_eq!(azerty.key(Binding::Send), ' ');
+
+    let dvorak = KeyBindings::preset(KeyboardLayout::Dvorak);
+    assert_eq!(dvorak.key(Binding::Ingredient(Ingredient::Egg)), '.');
+    assert_eq!(dvorak.game_key('.'), Some('e'));
+    // The mushrooms stay under the finger of the QWERTY 'h'
+    assert_eq!(dvorak.game_key('d'), Some('h'));
+}
+
+#[test]
+fn keys_are_translated_into_keys_of_the_game() {
+    let bindings = KeyBindings::preset(KeyboardLayout::Azerty);
+    assert_eq!(bindings.game_key('q'), Some('a'));
+    // Without a binding, 'a' would be taken for the bacon
+    assert_eq!(bindings.game_key('a'), None);
+    assert_eq!(bindings.game_key('7'), Some('7'));
+}
+
+#[test]
+fn a_key_is_only_bound_to_one_action() {
+    let mut bindings = KeyBindings::default();
+    assert_eq!(
+        bindings.bind(Binding::Ingredient(Ingredient::Bacon), 'c'),
+        Err(Binding::Ingredient(Ingredient::Cheese))
+    );
+    assert_eq!(bindings.layout, Some(KeyboardLayout::Qwerty));
+
+    assert_eq!(bindings.bind(Binding::Send, 'g'), Ok(()));
+    assert_eq!(bindings.layout, None);
+    assert_eq!(bindings.game_key('g'), Some(' '));
+    assert_eq!(bindings.game_key(' '), None);
+}
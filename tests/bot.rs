@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use miam::GameState;
+use miam::bot::Bot;
+use miam::headless::HeadlessApp;
+use miam::score::Score;
+
+#[test]
+fn flawless_bot_never_misses() {
+    let mut game = HeadlessApp::with_default_menu();
+    game.app.insert_resource(Bot::new(20., 0., false));
+    game.start(7, false);
+    game.advance(Duration::from_secs(60));
+
+    let score = *game.resource::<Score>();
+    assert_eq!(score.lives, 5);
+    assert!(score.streak > 5);
+}
+
+#[test]
+fn slow_bot_loses() {
+    let mut game = HeadlessApp::with_default_menu();
+    game.app.insert_resource(Bot::new(0.2, 0.2, false));
+    game.start(7, true);
+    game.advance(Duration::from_secs(120));
+
+    assert_eq!(game.state(), GameState::GameOver);
+}
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use miam::GameState;
+use miam::campaign::{Campaign, CampaignProgress};
+use miam::difficulty::GameMode;
+use miam::headless::{default_campaign, default_menu, HeadlessApp};
+use miam::ingredients::{Ingredient, Menu};
+use miam::order::MenuOnDisplay;
+
+#[test]
+fn every_day_can_be_served() {
+    let menu = default_menu();
+    let campaign = default_campaign();
+    for day in 0..campaign.days.len() {
+        let mode = GameMode::campaign(day, campaign.day_ingredients(day));
+        assert!(mode.validate(&menu).is_ok(), "day {}", day + 1);
+    }
+    // The last day serves the whole menu
+    assert_eq!(campaign.day_ingredients(campaign.days.len() - 1).len(), Ingredient::ALL.len() - 1);
+}
+
+#[test]
+fn invalid_campaigns_are_rejected() {
+    let day = "(goal: Burgers(5), unlocks: [Egg])";
+    let campaign = |first: &str, days: &str| format!("(first_ingredients: [{}], days: [{}])", first, days);
+    assert!(Campaign::from_bytes(campaign("Steak, Salad", day).as_bytes()).is_ok());
+    assert!(Campaign::from_bytes(campaign("Steak, Salad", "").as_bytes()).is_err());
+    assert!(Campaign::from_bytes(campaign("Steak, Bread", day).as_bytes()).is_err());
+    assert!(Campaign::from_bytes(campaign("Steak, Egg", day).as_bytes()).is_err());
+    assert!(Campaign::from_bytes(campaign("Steak", "(goal: Score(0), unlocks: [])").as_bytes()).is_err());
+}
+
+#[test]
+fn day_without_meat_cant_be_served() {
+    let menu = default_menu();
+    let mode = GameMode::campaign(0, vec![Ingredient::Salad, Ingredient::Tomato, Ingredient::Cheese, Ingredient::Egg, Ingredient::Onions]);
+    assert!(mode.validate(&menu).is_err());
+}
+
+#[test]
+fn days_unlock_ingredients_once() {
+    let campaign = default_campaign();
+    let mut progress = CampaignProgress::default();
+    assert!(progress.is_open(0, &campaign));
+    assert!(!progress.is_open(1, &campaign));
+
+    assert_eq!(progress.complete(0, &campaign), campaign.days[0].unlocks);
+    assert!(progress.is_open(1, &campaign));
+    assert!(progress.complete(0, &campaign).is_empty());
+    assert_eq!(progress.completed, 1);
+}
+
+#[test]
+fn restricted_menu_keeps_its_rules() {
+    let menu: Menu = default_menu().restricted(&[Ingredient::Steak, Ingredient::Salad, Ingredient::Mayo]);
+    assert_eq!(menu.ingredients, vec![Ingredient::Steak, Ingredient::Salad, Ingredient::Mayo]);
+    assert_eq!(menu.meats, vec![Ingredient::Steak]);
+    assert_eq!(menu.sauces, vec![Ingredient::Mayo]);
+}
+
+#[test]
+fn day_ends_when_its_goal_is_reached() {
+    let ingredients = default_campaign().day_ingredients(0);
+    let mut game = HeadlessApp::with_default_menu();
+    game.start_with_mode(21, GameMode::campaign(0, ingredients.clone()));
+    game.advance(Duration::from_millis(100));
+
+    // The first day asks for 5 burgers
+    for _ in 0..5 {
+        assert_eq!(game.state(), GameState::Cooking);
+        let keys = game.order_keys();
+        game.type_keys(&keys);
+        game.press(' ');
+        assert!(game.resource::<MenuOnDisplay>().ingredients.iter()
+            .all(|i| *i == Ingredient::Bread || ingredients.contains(i)));
+        game.advance(Duration::from_secs(3));
+    }
+    assert_eq!(game.state(), GameState::GameOver);
+}
@@ -0,0 +1,13 @@
+use std::time::Duration;
+
+use miam::cooking::ExpectingOrder;
+use miam::headless::HeadlessApp;
+
+/// Starts a classic game and waits for the first order
+pub fn start_game(seed: u64) -> HeadlessApp {
+    let mut game = HeadlessApp::with_default_menu();
+    game.start(seed, false);
+    game.advance(Duration::from_millis(100));
+    assert!(game.resource::<ExpectingOrder>().0);
+    game
+}
@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use miam::GameState;
+use miam::clock::GameClock;
+use miam::cooking::ExpectingOrder;
+use miam::order::{MenuOnDisplay, Order};
+use miam::restaurant::NextMenuChange;
+use miam::score::Score;
+
+mod common;
+
+use common::start_game;
+
+#[test]
+fn correct_burger_scores_points() {
+    let mut game = start_game(1);
+    let keys = game.order_keys();
+    game.type_keys(&keys);
+    game.press(' ');
+
+    let score = *game.resource::<Score>();
+    assert!(score.score > 0);
+    assert_eq!(score.streak, 1);
+    assert_eq!(score.lives, 5);
+    assert!(!game.resource::<ExpectingOrder>().0);
+
+    // The next customer comes in once the previous one has exited
+    game.advance(Duration::from_secs(1));
+    assert!(game.resource::<ExpectingOrder>().0);
+}
+
+#[test]
+fn wrong_burger_costs_a_life() {
+    let mut game = start_game(2);
+    game.type_keys("b ");
+
+    let score = *game.resource::<Score>();
+    assert_eq!(score.score, 0);
+    assert_eq!(score.streak, 0);
+    assert_eq!(score.lives, 4);
+}
+
+#[test]
+fn empty_burger_is_not_sent() {
+    let mut game = start_game(3);
+    game.press(' ');
+
+    assert!(game.resource::<ExpectingOrder>().0);
+    assert_eq!(game.resource::<Score>().lives, 5);
+}
+
+#[test]
+fn customer_leaves_when_out_of_time() {
+    let mut game = start_game(4);
+    game.advance(Duration::from_secs(60));
+
+    assert!(game.resource::<Score>().lives < 5);
+}
+
+#[test]
+fn game_over_after_five_misses() {
+    let mut game = start_game(5);
+    for _ in 0..5 {
+        assert_eq!(game.state(), GameState::Cooking);
+        game.type_keys("b ");
+        game.advance(Duration::from_secs(1));
+    }
+
+    assert_eq!(game.resource::<Score>().lives, 0);
+    assert_eq!(game.state(), GameState::GameOver);
+}
+
+#[test]
+fn same_seed_same_orders() {
+    let orders = |seed| {
+        let mut game = start_game(seed);
+        let mut orders = vec![];
+        for _ in 0..5 {
+            orders.push(game.resource::<Order>().ingredients.clone());
+            let keys = game.order_keys();
+            game.type_keys(&keys);
+            game.press(' ');
+            game.advance(Duration::from_secs(1));
+        }
+        orders
+    };
+
+    assert_eq!(orders(42), orders(42));
+}
+
+#[test]
+fn planned_menu_change_is_applied() {
+    let mut game = start_game(7);
+    let change = game.resource::<NextMenuChange>().0.expect("the next change is chosen in advance");
+    assert!(!game.resource::<MenuOnDisplay>().ingredients.contains(&change.ingredient));
+
+    game.advance(Duration::from_secs(10));
+    let menu = &game.resource::<MenuOnDisplay>().ingredients;
+    let position = change.replaced.unwrap_or(menu.len() - 1);
+    assert_eq!(menu[position], change.ingredient);
+}
+
+#[test]
+fn game_clock_only_runs_while_cooking() {
+    let mut game = start_game(11);
+    for _ in 0..5 {
+        game.type_keys("b ");
+        game.advance(Duration::from_secs(1));
+    }
+    assert_eq!(game.state(), GameState::GameOver);
+
+    let elapsed = game.resource::<GameClock>().elapsed();
+    game.advance(Duration::from_secs(2));
+    assert_eq!(game.resource::<GameClock>().elapsed(), elapsed);
+}
@@ -0,0 +1,24 @@
+use miam::daily::{daily_seed, DailyHistory, DailyResult};
+
+#[test]
+fn daily_seed_only_depends_on_the_date() {
+    assert_eq!(daily_seed("2022-08-12"), daily_seed("2022-08-12"));
+    // The seeds of two days in a row aren't close
+    assert!(daily_seed("2022-08-12").abs_diff(daily_seed("2022-08-13")) > 1000);
+    // Everyone gets the same seed, whatever their platform
+    assert_eq!(daily_seed("2022-08-12"), 886354126);
+}
+
+#[test]
+fn only_the_first_attempt_of_a_day_is_recorded() {
+    let mut history = DailyHistory::default();
+    assert!(history.start("2022-08-12"));
+    // An attempt counts as soon as it starts, even if it is never finished
+    assert_eq!(history.result("2022-08-12"), Some(&DailyResult { score: 0, burgers: 0 }));
+    history.finish("2022-08-12", DailyResult { score: 120, burgers: 8 });
+    assert!(!history.start("2022-08-12"));
+    assert!(history.start("2022-08-13"));
+
+    assert_eq!(history.result("2022-08-12").map(|r| r.score), Some(120));
+    assert_eq!(history.results.len(), 2);
+}
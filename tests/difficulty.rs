@@ -0,0 +1,16 @@
+use std::time::Duration;
+
+use miam::difficulty::Difficulty;
+use miam::headless::HeadlessApp;
+use miam::order::MenuOnDisplay;
+use miam::score::Score;
+
+#[test]
+fn difficulty_sets_lives_and_menu() {
+    let mut game = HeadlessApp::with_default_menu();
+    game.start_with_difficulty(6, Difficulty { lives: 3, full_menu: true, menu_size: 6, ..Difficulty::easy() });
+    game.advance(Duration::from_millis(100));
+
+    assert_eq!(game.resource::<Score>().lives, 3);
+    assert_eq!(game.resource::<MenuOnDisplay>().ingredients.len(), 6);
+}
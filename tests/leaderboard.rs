@@ -0,0 +1,30 @@
+use miam::leaderboard::{Leaderboard, LEADERBOARD_SIZE, LeaderboardEntry};
+
+fn entry(initials: &str, score: i64) -> LeaderboardEntry {
+    LeaderboardEntry {
+        initials: initials.to_string(),
+        score,
+        burgers: 0,
+        longest_chain: 0,
+        date: "2022-08-01".to_string(),
+    }
+}
+
+#[test]
+fn leaderboard_keeps_the_ten_best_scores_in_order() {
+    let mut leaderboard = Leaderboard::default();
+    for score in 1..=LEADERBOARD_SIZE as i64 {
+        leaderboard.insert(entry("AAA", score * 100));
+    }
+    assert_eq!(leaderboard.best_score(), 1000);
+    assert_eq!(leaderboard.rank(50), None);
+    assert_eq!(leaderboard.rank(0), None);
+
+    assert_eq!(leaderboard.insert(entry("BBB", 550)), Some(5));
+    assert_eq!(leaderboard.entries.len(), LEADERBOARD_SIZE);
+    assert_eq!(leaderboard.entries[5].initials, "BBB");
+    assert_eq!(leaderboard.entries.last().unwrap().score, 200);
+
+    // A tie goes below the existing entry
+    assert_eq!(leaderboard.rank(1000), Some(1));
+}
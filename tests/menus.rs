@@ -0,0 +1,29 @@
+use miam::ingredients::MenuBook;
+
+fn default_book() -> MenuBook {
+    MenuBook::from_bytes(include_bytes!("../assets/menus/default.menus.ron")).unwrap()
+}
+
+#[test]
+fn menus_are_chosen_by_name() {
+    let book = default_book();
+    assert!(book.menus.len() > 1);
+    assert_eq!(book.get("Menu Dos").map(|menu| menu.name.as_str()), Some("Menu Dos"));
+    assert!(book.get("Menu Tres").is_none());
+}
+
+#[test]
+fn next_menu_wraps_around() {
+    let book = default_book();
+    let first = &book.menus[0].name;
+    let last = &book.menus[book.menus.len() - 1].name;
+    assert_eq!(&book.next(first).name, &book.menus[1].name);
+    assert_eq!(&book.next(last).name, first);
+    assert_eq!(&book.next("unknown").name, first);
+}
+
+#[test]
+fn invalid_book_is_rejected() {
+    assert!(MenuBook::from_bytes(b"(menus: [])").is_err());
+    assert!(MenuBook::from_bytes(b"(menus: [(name: \"Broken\"").is_err());
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use miam::difficulty::Difficulty;
+use miam::headless::HeadlessApp;
+use miam::score::Score;
+
+#[test]
+fn almost_correct_burger_earns_partial_credit() {
+    let mut game = HeadlessApp::with_default_menu();
+    game.start_with_difficulty(8, Difficulty::easy());
+    game.advance(Duration::from_millis(100));
+
+    // Forget the second layer of the burger
+    let mut keys = game.order_keys();
+    keys.remove(1);
+    game.type_keys(&keys);
+    game.press(' ');
+
+    let score = *game.resource::<Score>();
+    assert_eq!(score.lives, Difficulty::easy().lives);
+    assert!(score.score > 0);
+    assert_eq!(score.streak, 0);
+}
+
+#[test]
+fn partial_burger_breaks_the_streak() {
+    let mut game = HeadlessApp::with_default_menu();
+    game.start_with_difficulty(12, Difficulty::easy());
+    game.advance(Duration::from_millis(100));
+
+    for _ in 0..3 {
+        let keys = game.order_keys();
+        game.type_keys(&keys);
+        game.press(' ');
+        game.advance(Duration::from_secs(1));
+    }
+    assert_eq!(game.resource::<Score>().streak, 3);
+
+    let before = game.resource::<Score>().score;
+    let mut keys = game.order_keys();
+    keys.remove(1);
+    game.type_keys(&keys);
+    game.press(' ');
+
+    let score = *game.resource::<Score>();
+    assert_eq!(score.streak, 0);
+    assert_eq!(score.lives, Difficulty::easy().lives);
+    assert!(score.score > before);
+}
+
+#[test]
+fn partial_credit_ignores_the_streak() {
+    let difficulty = Difficulty::easy();
+    let mut first = Score::default();
+    let mut chained = Score { streak: 4, ..Score::default() };
+    first.compute_on_partial(2., 5, 0.8, &difficulty);
+    chained.compute_on_partial(2., 5, 0.8, &difficulty);
+
+    assert!(first.score > 0);
+    assert_eq!(chained.score, first.score);
+    assert_eq!(chained.streak, 0);
+}
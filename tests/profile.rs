@@ -0,0 +1,29 @@
+use miam::ingredients::Ingredient;
+use miam::profile::Profile;
+use miam::stats::RunStats;
+
+fn game(burgers: u32, time_cooked: f64) -> RunStats {
+    let mut stats = RunStats { burgers, time_cooked, ..RunStats::default() };
+    stats.placed_ingredients.insert(Ingredient::Bread, burgers * 2);
+    stats.placed_ingredients.insert(Ingredient::Cheese, burgers);
+    stats
+}
+
+#[test]
+fn profile_adds_up_games_and_weeks() {
+    let mut profile = Profile::default();
+    profile.record(&game(10, 60.), "classic", "2022-08-01".to_string());
+    profile.record(&game(20, 60.), "madness", "2022-08-10".to_string());
+    profile.record(&game(30, 120.), "madness", "2022-08-10".to_string());
+
+    assert_eq!(profile.games, 3);
+    assert_eq!(profile.burgers, 60);
+    assert_eq!(profile.days.len(), 2);
+    assert_eq!(profile.favorite_mode(), Some("madness"));
+    assert_eq!(profile.favorite_ingredients(1), vec![(Ingredient::Bread, 120)]);
+
+    let this_week = profile.week("2022-08-12", 0);
+    assert_eq!(this_week.burgers, 50);
+    assert_eq!(this_week.burgers_per_minute(), Some(50. / 3.));
+    assert_eq!(profile.week("2022-08-12", 1).burgers, 10);
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use miam::GameState;
+use miam::difficulty::GameMode;
+use miam::headless::HeadlessApp;
+use miam::replay::{Playback, ReplayRecorder};
+use miam::score::Score;
+use miam::stats::RunStats;
+use miam::tutorial::{Tutorial, TutorialStep};
+
+#[test]
+fn replayed_tutorial_ends_like_the_game() {
+    let mut game = HeadlessApp::with_default_menu();
+    game.app.insert_resource(Tutorial::default());
+    game.start_with_mode(15, GameMode::tutorial());
+    game.advance(Duration::from_millis(100));
+    let keys = game.order_keys();
+    game.type_keys(&keys);
+    game.press(' ');
+    game.advance(Duration::from_secs(1));
+    game.type_keys("b<");
+    game.advance(Duration::from_secs(17));
+    assert_eq!(game.state(), GameState::TitleScreen);
+
+    let replay = game.resource::<ReplayRecorder>().replay().clone();
+    let frames = replay.frames.len();
+    game.play(replay);
+    assert_eq!(game.state(), GameState::Cooking);
+    assert_eq!(game.resource::<Tutorial>().step, TutorialStep::Bread);
+
+    // The guide follows the replayed keys to the last step, then the game goes back to the title screen
+    for _ in 0..frames {
+        game.update();
+    }
+    assert_eq!(game.state(), GameState::TitleScreen);
+    assert!(!game.app.world.contains_resource::<Playback>());
+}
+
+#[test]
+fn replay_ends_on_the_same_frame_send() {
+    let mut game = HeadlessApp::with_default_menu();
+    game.start(4, false);
+    game.advance(Duration::from_millis(100));
+
+    // Whole burgers are stacked and sent in a single frame, then wrong ones until the last life is lost
+    for _ in 0..3 {
+        let keys = game.order_keys();
+        game.press_together(&format!("{} ", keys));
+        game.advance(Duration::from_secs(1));
+    }
+    while game.state() == GameState::Cooking {
+        game.press_together("b ");
+        game.advance(Duration::from_millis(500));
+    }
+    let score = *game.resource::<Score>();
+    let burgers = game.resource::<RunStats>().burgers;
+    let replay = game.resource::<ReplayRecorder>().replay().clone();
+    let frames = replay.frames.len();
+    // The last keys of the replay were stacked and sent in the same frame
+    let last: Vec<char> = replay.events.iter()
+        .filter(|event| event.frame == replay.events.last().unwrap().frame)
+        .map(|event| event.key)
+        .collect();
+    assert_eq!(last, vec!['b', ' ']);
+
+    game.play(replay);
+    let mut played = 1;
+    while game.state() == GameState::Cooking {
+        game.update();
+        played += 1;
+    }
+    assert_eq!(played, frames);
+    assert_eq!(*game.resource::<Score>(), score);
+    assert_eq!(game.resource::<RunStats>().burgers, burgers);
+}
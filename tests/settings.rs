@@ -0,0 +1,13 @@
+use miam::settings::Settings;
+
+#[test]
+fn muting_silences_channels_without_losing_volumes() {
+    let mut settings = Settings { bgm_volume: 80, ..Settings::default() };
+    settings.toggle_mute();
+    assert_eq!(settings.bgm_volume(), 0.);
+    assert_eq!(settings.sfx_volume(), 0.);
+
+    settings.toggle_mute();
+    assert_eq!(settings.bgm_volume(), 0.8);
+    assert_eq!(settings.sfx_volume(), 0.3);
+}
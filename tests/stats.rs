@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use miam::difficulty::Difficulty;
+use miam::headless::HeadlessApp;
+use miam::stats::RunStats;
+
+mod common;
+
+use common::start_game;
+
+#[test]
+fn run_stats_count_burgers_and_keystrokes() {
+    let mut game = start_game(9);
+    let keys = game.order_keys();
+    game.type_keys(&keys);
+    game.press(' ');
+    game.advance(Duration::from_secs(1));
+    game.type_keys("b ");
+
+    let stats = game.resource::<RunStats>();
+    assert_eq!(stats.burgers, 1);
+    assert_eq!(stats.misses, 1);
+    assert_eq!(stats.out_of_time, 0);
+    assert_eq!(stats.longest_chain, 1);
+    assert_eq!(stats.keystrokes as usize, keys.len() + 1);
+    assert_eq!(stats.keystroke_accuracy(), Some(1.));
+    assert!(stats.time_per_ingredient().is_some());
+}
+
+#[test]
+fn partial_burger_ends_the_chain() {
+    let mut game = HeadlessApp::with_default_menu();
+    game.start_with_difficulty(12, Difficulty::easy());
+    game.advance(Duration::from_millis(100));
+    for _ in 0..2 {
+        let keys = game.order_keys();
+        game.type_keys(&keys);
+        game.press(' ');
+        game.advance(Duration::from_secs(1));
+    }
+
+    // Forget the second layer of the burger
+    let mut keys = game.order_keys();
+    keys.remove(1);
+    game.type_keys(&keys);
+    game.press(' ');
+    game.advance(Duration::from_secs(1));
+
+    let keys = game.order_keys();
+    game.type_keys(&keys);
+    game.press(' ');
+
+    let stats = game.resource::<RunStats>();
+    assert_eq!(stats.burgers, 4);
+    assert_eq!(stats.chain, 1);
+    assert_eq!(stats.longest_chain, 2);
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use miam::GameState;
+use miam::clock::GameClock;
+use miam::difficulty::GameMode;
+use miam::headless::HeadlessApp;
+use miam::score::Score;
+
+#[test]
+fn time_attack_ends_with_the_clock_not_the_lives() {
+    let mut game = HeadlessApp::with_default_menu();
+    game.start_with_mode(13, GameMode::time_attack());
+    game.advance(Duration::from_millis(100));
+    for _ in 0..6 {
+        game.type_keys("b ");
+        game.advance(Duration::from_secs(1));
+    }
+    assert_eq!(game.state(), GameState::Cooking);
+
+    game.advance(Duration::from_secs(120));
+    assert_eq!(game.state(), GameState::GameOver);
+}
+
+#[test]
+fn chains_add_time_in_time_attack() {
+    let mut game = HeadlessApp::with_default_menu();
+    game.start_with_mode(14, GameMode::time_attack());
+    game.advance(Duration::from_millis(100));
+    for _ in 0..5 {
+        let keys = game.order_keys();
+        game.type_keys(&keys);
+        game.press(' ');
+        game.advance(Duration::from_secs(1));
+    }
+
+    let score = *game.resource::<Score>();
+    assert_eq!(score.streak, 5);
+    assert_eq!(score.bonus_time, Duration::from_secs(5));
+    let time_left = score.time_left(game.resource::<GameMode>(), game.resource::<GameClock>().elapsed()).unwrap();
+    assert!(time_left > Duration::from_secs(120) - game.resource::<GameClock>().elapsed());
+}
@@ -0,0 +1,34 @@
+use std::time::Duration;
+
+use miam::GameState;
+use miam::difficulty::GameMode;
+use miam::headless::HeadlessApp;
+use miam::tutorial::{Tutorial, TutorialStep};
+
+#[test]
+fn tutorial_goes_through_its_steps() {
+    let mut game = HeadlessApp::with_default_menu();
+    game.app.insert_resource(Tutorial::default());
+    game.start_with_mode(15, GameMode::tutorial());
+    game.advance(Duration::from_millis(100));
+    assert_eq!(game.resource::<Tutorial>().step, TutorialStep::Bread);
+
+    // The order starts with bread and contains a meat, completing both steps
+    let keys = game.order_keys();
+    game.type_keys(&keys);
+    assert_eq!(game.resource::<Tutorial>().step, TutorialStep::Send);
+    game.press(' ');
+    assert_eq!(game.resource::<Tutorial>().step, TutorialStep::Clear);
+
+    game.advance(Duration::from_secs(1));
+    game.type_keys("b<");
+    assert_eq!(game.resource::<Tutorial>().step, TutorialStep::Chef);
+
+    // The chef writes on the menu every 10 seconds
+    game.advance(Duration::from_secs(10));
+    assert_eq!(game.resource::<Tutorial>().step, TutorialStep::Done);
+
+    game.advance(Duration::from_secs(7));
+    assert_eq!(game.state(), GameState::TitleScreen);
+    assert!(!game.app.world.contains_resource::<Tutorial>());
+}
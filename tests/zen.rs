@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use miam::GameState;
+use miam::cooking::ExpectingOrder;
+use miam::difficulty::GameMode;
+use miam::headless::HeadlessApp;
+use miam::stats::RunStats;
+
+#[test]
+fn zen_game_never_ends() {
+    let mut game = HeadlessApp::with_default_menu();
+    game.start_with_mode(12, GameMode::zen());
+    game.advance(Duration::from_secs(60));
+    assert!(game.resource::<ExpectingOrder>().0);
+
+    for _ in 0..6 {
+        game.type_keys("b ");
+        game.advance(Duration::from_secs(1));
+    }
+    assert_eq!(game.state(), GameState::Cooking);
+    assert!(game.resource::<ExpectingOrder>().0);
+    assert!(game.resource::<RunStats>().ingredients_per_minute().unwrap() > 0.);
+}